@@ -0,0 +1,165 @@
+// Betweenness centrality using Brandes' algorithm
+// Useful resources:
+// https://en.wikipedia.org/wiki/Betweenness_centrality
+// https://www.eecs.wsu.edu/~assefaw/CptS580-06/papers/brandes01centrality.pdf
+//
+// Graphs are undirected (as in `graph_map_to_matrix`), so each unordered pair of nodes is counted once.
+
+use crate::dijkstra::graph_matrix;
+use crate::dijkstra::shortest_path_dag::shortest_path_dag;
+use crate::graph_type::*;
+use std::collections::HashMap;
+
+/// Node and edge betweenness of a matrix based graph.
+/// Edge keys are ordered as `(smaller, larger)` node numbers.
+pub fn betweenness(graph_matrix: &GraphMatrix) -> (Vec<f64>, HashMap<(usize, usize), f64>) {
+    let node_count = graph_matrix.len();
+    let mut node_centrality = vec![0.0; node_count];
+    let mut edge_centrality: HashMap<(usize, usize), f64> = HashMap::new();
+
+    for source in 0..node_count {
+        let dag = shortest_path_dag(graph_matrix, source).unwrap();
+
+        // reachable nodes, farthest first
+        let mut order: Vec<usize> = (0..node_count)
+            .filter(|&node| dag[node].distance != u32::MAX)
+            .collect();
+        order.sort_by_key(|&node| std::cmp::Reverse(dag[node].distance));
+
+        // accumulating the dependencies of the source on every node
+        let mut dependency = vec![0.0; node_count];
+        for &node in order.iter() {
+            let node_paths = dag[node].path_count as f64;
+            for &previous in dag[node].previous.iter() {
+                let share = dag[previous].path_count as f64 / node_paths * (1.0 + dependency[node]);
+                dependency[previous] += share;
+
+                let edge = (previous.min(node), previous.max(node));
+                *edge_centrality.entry(edge).or_insert(0.0) += share;
+            }
+            if node != source {
+                node_centrality[node] += dependency[node];
+            }
+        }
+    }
+
+    // every pair was visited from both of its ends
+    for value in node_centrality.iter_mut() {
+        *value /= 2.0;
+    }
+    for value in edge_centrality.values_mut() {
+        *value /= 2.0;
+    }
+
+    (node_centrality, edge_centrality)
+}
+
+/// Node betweenness keyed by node names.
+/// With `normalized` the values are divided by the number of pairs not containing the node.
+pub fn betweenness_centrality(graph_map: &GraphMap, normalized: bool) -> HashMap<String, f64> {
+    let (names, matrix) = graph_matrix::graph_map_to_matrix(graph_map);
+    let (node_centrality, _) = betweenness(&matrix);
+
+    let node_count = names.len() as f64;
+    let pairs = (node_count - 1.0) * (node_count - 2.0) / 2.0;
+    let scale = if normalized && pairs > 0.0 {
+        1.0 / pairs
+    } else {
+        1.0
+    };
+
+    names
+        .into_iter()
+        .zip(node_centrality)
+        .map(|(name, value)| (name, value * scale))
+        .collect()
+}
+
+/// Edge betweenness keyed by node name pairs, the smaller name comes first.
+/// With `normalized` the values are divided by the number of node pairs.
+pub fn edge_betweenness_centrality(
+    graph_map: &GraphMap,
+    normalized: bool,
+) -> HashMap<(String, String), f64> {
+    let (names, matrix) = graph_matrix::graph_map_to_matrix(graph_map);
+    let (_, edge_centrality) = betweenness(&matrix);
+
+    let node_count = names.len() as f64;
+    let pairs = node_count * (node_count - 1.0) / 2.0;
+    let scale = if normalized && pairs > 0.0 {
+        1.0 / pairs
+    } else {
+        1.0
+    };
+
+    edge_centrality
+        .into_iter()
+        .map(|((a, b), value)| ((names[a].clone(), names[b].clone()), value * scale))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_samples;
+
+    fn edge(a: &str, b: &str) -> (String, String) {
+        (a.to_string(), b.to_string())
+    }
+
+    #[test]
+    fn betweenness_path_test() {
+        let graph_map = graph_samples::build_undirected_graph(&[("A", "B", 1), ("B", "C", 1)]);
+
+        let centrality = betweenness_centrality(&graph_map, false);
+        assert_eq!(centrality["A"], 0.0);
+        assert_eq!(centrality["B"], 1.0);
+        assert_eq!(centrality["C"], 0.0);
+
+        let edge_centrality = edge_betweenness_centrality(&graph_map, false);
+        assert_eq!(edge_centrality[&edge("A", "B")], 2.0);
+        assert_eq!(edge_centrality[&edge("B", "C")], 2.0);
+
+        let edge_centrality = edge_betweenness_centrality(&graph_map, true);
+        assert!((edge_centrality[&edge("A", "B")] - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn betweenness_equal_cost_test() {
+        // square A-B-C-D-A, both routes between opposite corners are shortest
+        let graph_map = graph_samples::build_undirected_graph(&[
+            ("A", "B", 1),
+            ("B", "C", 1),
+            ("C", "D", 1),
+            ("D", "A", 1),
+        ]);
+
+        let centrality = betweenness_centrality(&graph_map, false);
+        for name in ["A", "B", "C", "D"] {
+            assert_eq!(centrality[name], 0.5);
+        }
+
+        let edge_centrality = edge_betweenness_centrality(&graph_map, false);
+        assert_eq!(edge_centrality.len(), 4);
+        assert_eq!(edge_centrality[&edge("A", "B")], 2.0);
+    }
+
+    #[test]
+    fn betweenness_sample_test() {
+        let (graph_map, _) = graph_samples::sample2();
+
+        let centrality = betweenness_centrality(&graph_map, true);
+        // D lies on every shortest path leaving A
+        let max = centrality
+            .iter()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .unwrap();
+        assert_eq!(max.0, "D");
+        assert!(centrality.values().all(|x| (0.0..=1.0).contains(x)));
+
+        // A-B is never a shortest path, A->D->B is shorter
+        let edge_centrality = edge_betweenness_centrality(&graph_map, false);
+        assert!(!edge_centrality.contains_key(&edge("A", "B")));
+        assert_eq!(edge_centrality.len(), 6);
+    }
+}
@@ -0,0 +1,105 @@
+// Closeness and harmonic centrality
+// Useful resources:
+// https://en.wikipedia.org/wiki/Closeness_centrality
+//
+// Closeness uses the Wasserman and Faust formula, so unreachable nodes scale the score down
+// instead of making it zero: (r - 1) / sum * (r - 1) / (n - 1), r being the reachable node count.
+// Harmonic centrality sums 1 / distance, unreachable nodes contribute zero.
+
+use crate::dijkstra::graph_matrix;
+use crate::graph_type::*;
+use std::collections::HashMap;
+
+/// Closeness of every node from an all-pairs distance table
+pub fn closeness_from_distances(distances: &GraphMatrix) -> Vec<f64> {
+    let node_count = distances.len();
+    distances
+        .iter()
+        .map(|row| {
+            let reachable: Vec<u64> = row
+                .iter()
+                .filter(|&&x| x != u32::MAX && x > 0)
+                .map(|&x| x as u64)
+                .collect();
+            let total: u64 = reachable.iter().sum();
+            if total == 0 {
+                return 0.0;
+            }
+
+            let reached = reachable.len() as f64;
+            reached / total as f64 * reached / (node_count - 1) as f64
+        })
+        .collect()
+}
+
+/// Harmonic centrality of every node from an all-pairs distance table
+pub fn harmonic_from_distances(distances: &GraphMatrix) -> Vec<f64> {
+    distances
+        .iter()
+        .map(|row| {
+            row.iter()
+                .filter(|&&x| x != u32::MAX && x > 0)
+                .map(|&x| 1.0 / x as f64)
+                .sum()
+        })
+        .collect()
+}
+
+pub fn closeness_centrality(graph_map: &GraphMap) -> HashMap<String, f64> {
+    let (names, matrix) = graph_matrix::graph_map_to_matrix(graph_map);
+    let distances = graph_matrix::all_pairs_distances(&matrix);
+    names
+        .into_iter()
+        .zip(closeness_from_distances(&distances))
+        .collect()
+}
+
+pub fn harmonic_centrality(graph_map: &GraphMap) -> HashMap<String, f64> {
+    let (names, matrix) = graph_matrix::graph_map_to_matrix(graph_map);
+    let distances = graph_matrix::all_pairs_distances(&matrix);
+    names
+        .into_iter()
+        .zip(harmonic_from_distances(&distances))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_samples;
+
+    #[test]
+    fn closeness_sample1_test() {
+        let (graph_map, expected_shortest_paths) = graph_samples::sample1();
+        let closeness = closeness_centrality(&graph_map);
+
+        // distances from A are listed in the sample
+        let total: u32 = expected_shortest_paths.iter().map(|x| x.distance).sum();
+        assert!((closeness["A"] - 8.0 / total as f64).abs() < 1e-9);
+
+        let harmonic = harmonic_centrality(&graph_map);
+        let expected: f64 = expected_shortest_paths
+            .iter()
+            .filter(|x| x.distance > 0)
+            .map(|x| 1.0 / x.distance as f64)
+            .sum();
+        assert!((harmonic["A"] - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unreachable_test() {
+        // 0-1 connected, 2 isolated
+        let distances = vec![
+            vec![0, 2, u32::MAX],
+            vec![2, 0, u32::MAX],
+            vec![u32::MAX, u32::MAX, 0],
+        ];
+
+        let closeness = closeness_from_distances(&distances);
+        assert!((closeness[0] - 0.25).abs() < 1e-9);
+        assert_eq!(closeness[2], 0.0);
+
+        let harmonic = harmonic_from_distances(&distances);
+        assert_eq!(harmonic, [0.5, 0.5, 0.0]);
+    }
+}
@@ -0,0 +1,241 @@
+// Connected and strongly connected components
+// Useful resources:
+// https://en.wikipedia.org/wiki/Component_(graph_theory)
+// https://en.wikipedia.org/wiki/Tarjan%27s_strongly_connected_components_algorithm
+//
+// Component ids are numbered from zero. Weak component ids follow the sorted node names,
+// strong component ids come out of Tarjan's algorithm in reverse topological order.
+// Edge targets missing from the graph keys are still treated as nodes.
+
+use crate::graph_type::*;
+use crate::mst::kruskal::DisjointSet;
+use std::collections::HashMap;
+
+/// Numbered adjacency lists of a map based graph, with the sorted node names
+pub fn graph_map_to_adjacency(graph_map: &GraphMap) -> (Vec<String>, Vec<Vec<usize>>) {
+    let targets = graph_map.values().flat_map(|x| x.keys());
+    let names = NodeNames::sorted(graph_map.keys().chain(targets));
+
+    let index = |name: &String| names.id(name).unwrap().index();
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); names.len()];
+    for (node, edges) in graph_map {
+        let node = index(node);
+        adjacency[node] = edges.keys().map(index).collect();
+        adjacency[node].sort();
+    }
+
+    (names.into_names(), adjacency)
+}
+
+/// Numbered adjacency lists of a vector based graph, with the sorted node names
+pub fn graph_vector_to_adjacency(graph_vector: &GraphVector) -> (Vec<String>, Vec<Vec<usize>>) {
+    let names = NodeNames::sorted(
+        graph_vector
+            .iter()
+            .flat_map(|(node, edges)| std::iter::once(node).chain(edges.iter().map(|x| &x.0))),
+    );
+
+    let index = |name: &String| names.id(name).unwrap().index();
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); names.len()];
+    for (node, edges) in graph_vector {
+        let node = index(node);
+        adjacency[node].extend(edges.iter().map(|x| index(&x.0)));
+        adjacency[node].sort();
+    }
+
+    (names.into_names(), adjacency)
+}
+
+/// Weakly connected components, edge direction is ignored
+pub fn weak_components(adjacency: &[Vec<usize>]) -> Vec<usize> {
+    let node_count = adjacency.len();
+    let mut disjoint_set = DisjointSet::new(node_count);
+    for (node, edges) in adjacency.iter().enumerate() {
+        for &next in edges {
+            disjoint_set.union(node, next);
+        }
+    }
+
+    let mut root_ids: HashMap<usize, usize> = HashMap::new();
+    (0..node_count)
+        .map(|node| {
+            let root = disjoint_set.find(node);
+            let next_id = root_ids.len();
+            *root_ids.entry(root).or_insert(next_id)
+        })
+        .collect()
+}
+
+/// Strongly connected components using an iterative version of Tarjan's algorithm
+pub fn strong_components(adjacency: &[Vec<usize>]) -> Vec<usize> {
+    let node_count = adjacency.len();
+    let mut index = vec![usize::MAX; node_count];
+    let mut low_link = vec![0; node_count];
+    let mut on_stack = vec![false; node_count];
+    let mut stack: Vec<usize> = Vec::new();
+    let mut component = vec![usize::MAX; node_count];
+    let mut next_index = 0;
+    let mut next_component = 0;
+
+    for root in 0..node_count {
+        if index[root] != usize::MAX {
+            continue;
+        }
+
+        // emulating the recursion, each frame keeps the position of the next edge to follow
+        let mut calls: Vec<(usize, usize)> = vec![(root, 0)];
+        index[root] = next_index;
+        low_link[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some(&(node, edge)) = calls.last() {
+            if edge < adjacency[node].len() {
+                calls.last_mut().unwrap().1 += 1;
+                let next = adjacency[node][edge];
+                if index[next] == usize::MAX {
+                    index[next] = next_index;
+                    low_link[next] = next_index;
+                    next_index += 1;
+                    stack.push(next);
+                    on_stack[next] = true;
+                    calls.push((next, 0));
+                } else if on_stack[next] {
+                    low_link[node] = low_link[node].min(index[next]);
+                }
+                continue;
+            }
+
+            calls.pop();
+            if let Some(&(parent, _)) = calls.last() {
+                low_link[parent] = low_link[parent].min(low_link[node]);
+            }
+
+            // the node is the root of a component
+            if low_link[node] == index[node] {
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component[member] = next_component;
+                    if member == node {
+                        break;
+                    }
+                }
+                next_component += 1;
+            }
+        }
+    }
+
+    component
+}
+
+fn components_named(names: Vec<String>, components: Vec<usize>) -> HashMap<String, usize> {
+    names.into_iter().zip(components).collect()
+}
+
+pub fn connected_components(graph_map: &GraphMap) -> HashMap<String, usize> {
+    let (names, adjacency) = graph_map_to_adjacency(graph_map);
+    components_named(names, weak_components(&adjacency))
+}
+
+pub fn strongly_connected_components(graph_map: &GraphMap) -> HashMap<String, usize> {
+    let (names, adjacency) = graph_map_to_adjacency(graph_map);
+    components_named(names, strong_components(&adjacency))
+}
+
+pub fn connected_components_vector(graph_vector: &GraphVector) -> HashMap<String, usize> {
+    let (names, adjacency) = graph_vector_to_adjacency(graph_vector);
+    components_named(names, weak_components(&adjacency))
+}
+
+pub fn strongly_connected_components_vector(graph_vector: &GraphVector) -> HashMap<String, usize> {
+    let (names, adjacency) = graph_vector_to_adjacency(graph_vector);
+    components_named(names, strong_components(&adjacency))
+}
+
+/// Checks that a path between `from` and `to` may exist, without running any search.
+/// `components` are the weakly connected components of the graph.
+pub fn check_reachability(
+    components: &HashMap<String, usize>,
+    from: &str,
+    to: &str,
+) -> Result<(), ShortestPathError> {
+    let from_component = components
+        .get(from)
+        .ok_or_else(|| ShortestPathError::MissingNode(from.to_string()))?;
+    let to_component = components
+        .get(to)
+        .ok_or_else(|| ShortestPathError::MissingNode(to.to_string()))?;
+
+    if from_component != to_component {
+        return Err(ShortestPathError::DifferentComponents);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::graph_vector::graph_map_to_vector;
+    use crate::graph_samples;
+
+    #[test]
+    fn connected_sample_test() {
+        let (graph_map, _) = graph_samples::sample1();
+        let components = connected_components(&graph_map);
+        assert_eq!(components.len(), 9);
+        assert!(components.values().all(|&x| x == 0));
+
+        let strong = strongly_connected_components(&graph_map);
+        assert!(strong.values().all(|&x| x == strong["A"]));
+    }
+
+    #[test]
+    fn components_test() {
+        // cycle A->B->C->A, then C->D, D<->E, and a separate pair X<->Y
+        let graph_map = graph_samples::build_directed_graph(&[
+            ("A", "B", 1),
+            ("B", "C", 1),
+            ("C", "A", 1),
+            ("C", "D", 1),
+            ("D", "E", 1),
+            ("E", "D", 1),
+            ("X", "Y", 1),
+            ("Y", "X", 1),
+        ]);
+
+        let weak = connected_components(&graph_map);
+        assert_eq!(weak["A"], 0);
+        assert_eq!(weak["E"], 0);
+        assert_eq!(weak["X"], 1);
+        assert_eq!(weak["Y"], 1);
+
+        let strong = strongly_connected_components(&graph_map);
+        assert_eq!(strong["A"], strong["B"]);
+        assert_eq!(strong["A"], strong["C"]);
+        assert_eq!(strong["D"], strong["E"]);
+        assert_ne!(strong["A"], strong["D"]);
+        assert_ne!(strong["A"], strong["X"]);
+        // reverse topological order: D/E before A/B/C
+        assert!(strong["D"] < strong["A"]);
+
+        let graph_vector = graph_map_to_vector(&graph_map).unwrap();
+        assert_eq!(connected_components_vector(&graph_vector), weak);
+        assert_eq!(strongly_connected_components_vector(&graph_vector), strong);
+    }
+
+    #[test]
+    fn check_reachability_test() {
+        let graph_map = graph_samples::build_directed_graph(&[("A", "B", 1), ("X", "Y", 1)]);
+        let components = connected_components(&graph_map);
+
+        assert_eq!(check_reachability(&components, "A", "B"), Ok(()));
+        let error = check_reachability(&components, "A", "Y").unwrap_err();
+        assert_eq!(error, ShortestPathError::DifferentComponents);
+        assert_eq!(error.to_string(), "unreachable: different components");
+        assert_eq!(
+            check_reachability(&components, "A", "Z"),
+            Err(ShortestPathError::MissingNode("Z".to_string()))
+        );
+    }
+}
@@ -0,0 +1,94 @@
+// Graph metrics: eccentricity, diameter, radius, center and periphery
+// Useful resources:
+// https://en.wikipedia.org/wiki/Distance_(graph_theory)
+//
+// Disconnected graphs follow the usual convention of infinite distances:
+// every eccentricity is `u32::MAX`, so are the diameter and radius,
+// and both center and periphery contain all nodes.
+
+use crate::dijkstra::graph_matrix;
+use crate::graph_type::*;
+
+/// Computes the metrics from an all-pairs distance table (see `graph_matrix::all_pairs_distances`)
+pub fn graph_metrics_from_distances(distances: &GraphMatrix) -> GraphMetrics {
+    let eccentricity: Vec<u32> = distances
+        .iter()
+        .map(|row| row.iter().copied().max().unwrap_or(0))
+        .collect();
+
+    let diameter = eccentricity.iter().copied().max().unwrap_or(0);
+    let radius = eccentricity.iter().copied().min().unwrap_or(0);
+
+    let center = (0..eccentricity.len())
+        .filter(|&node| eccentricity[node] == radius)
+        .collect();
+    let periphery = (0..eccentricity.len())
+        .filter(|&node| eccentricity[node] == diameter)
+        .collect();
+
+    GraphMetrics {
+        eccentricity,
+        diameter,
+        radius,
+        center,
+        periphery,
+    }
+}
+
+pub fn graph_metrics(graph_matrix: &GraphMatrix) -> GraphMetrics {
+    graph_metrics_from_distances(&graph_matrix::all_pairs_distances(graph_matrix))
+}
+
+/// Computes the metrics of a map based graph, node numbers refer to the returned names
+pub fn graph_metrics_named(graph_map: &GraphMap) -> (Vec<String>, GraphMetrics) {
+    let (names, matrix) = graph_matrix::graph_map_to_matrix(graph_map);
+    (names, graph_metrics(&matrix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_samples;
+
+    fn names_of(names: &[String], nodes: &[usize]) -> Vec<String> {
+        nodes.iter().map(|x| names[*x].clone()).collect()
+    }
+
+    #[test]
+    fn graph_metrics_sample2_test() {
+        let (graph_map, _) = graph_samples::sample2();
+        let (names, metrics) = graph_metrics_named(&graph_map);
+        assert_eq!(names, ["A", "B", "C", "D", "E"]);
+
+        // A:7 (C), B:5 (C), C:7 (A), D:6 (C), E:5 (C)
+        assert_eq!(metrics.eccentricity, [7, 5, 7, 6, 5]);
+        assert_eq!(metrics.diameter, 7);
+        assert_eq!(metrics.radius, 5);
+        assert_eq!(names_of(&names, &metrics.center), ["B", "E"]);
+        assert_eq!(names_of(&names, &metrics.periphery), ["A", "C"]);
+    }
+
+    #[test]
+    fn graph_metrics_disconnected_test() {
+        let graph_matrix = vec![
+            vec![0, 2, 0, 0],
+            vec![2, 0, 0, 0],
+            vec![0, 0, 0, 3],
+            vec![0, 0, 3, 0],
+        ];
+        let metrics = graph_metrics(&graph_matrix);
+        assert_eq!(metrics.eccentricity, [u32::MAX; 4]);
+        assert_eq!(metrics.diameter, u32::MAX);
+        assert_eq!(metrics.radius, u32::MAX);
+        assert_eq!(metrics.center, [0, 1, 2, 3]);
+        assert_eq!(metrics.periphery, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn graph_metrics_empty_test() {
+        let metrics = graph_metrics(&GraphMatrix::new());
+        assert!(metrics.eccentricity.is_empty());
+        assert_eq!(metrics.diameter, 0);
+        assert!(metrics.center.is_empty());
+    }
+}
@@ -0,0 +1,4 @@
+pub mod metrics;
+pub mod betweenness;
+pub mod closeness;
+pub mod components;
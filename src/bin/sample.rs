@@ -6,6 +6,6 @@ fn main() {
 
     let from = "A";
     let to = "I";
-    let shortest_path = dijkstra::graph_map::shortest_path(&graph, &from, &to);
+    let shortest_path = dijkstra::graph_map::shortest_path(&graph, from, to);
     println!("Shortest path from {from} to {to} is :\n {:?}\n", shortest_path);
 }
\ No newline at end of file
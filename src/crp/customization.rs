@@ -0,0 +1,194 @@
+// Customization of the overlay for one metric
+// For every cell the distances between its boundary nodes are computed, staying inside the cell:
+// - level 0 cells are searched on the original edges,
+// - higher cells are searched on the level below: the cliques of their subcells plus
+//   the original edges between those subcells.
+// A new metric only needs a new customization, the partition stays as it is.
+
+use crate::crp::partition::Overlay;
+use crate::graph_type::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Edge of a local search as `(to, distance, tag)`,
+/// the tag is 0 for original edges and `level + 1` for clique edges of a cell at `level`
+pub(crate) type TaggedEdge = (usize, u32, usize);
+
+/// Node reached by a local search as `(distance, previous, tag of the edge from previous)`
+pub(crate) type SearchRecord = (u32, usize, usize);
+
+/// Dijkstra over the edges given by `edges`, stopping once `target` is settled
+pub(crate) fn local_search(
+    source: usize,
+    target: Option<usize>,
+    mut edges: impl FnMut(usize, &mut Vec<TaggedEdge>),
+) -> HashMap<usize, SearchRecord> {
+    let mut records: HashMap<usize, SearchRecord> = HashMap::from([(source, (0, source, 0))]);
+    let mut heap: BinaryHeap<Reverse<(u32, usize)>> = BinaryHeap::from([Reverse((0, source))]);
+    let mut node_edges: Vec<TaggedEdge> = Vec::new();
+
+    while let Some(Reverse((distance, node))) = heap.pop() {
+        if distance > records[&node].0 {
+            continue;
+        }
+        if Some(node) == target {
+            break;
+        }
+
+        node_edges.clear();
+        edges(node, &mut node_edges);
+        for &(to, weight, tag) in node_edges.iter() {
+            let new_distance = distance.saturating_add(weight);
+            if records
+                .get(&to)
+                .is_none_or(|record| new_distance < record.0)
+            {
+                records.insert(to, (new_distance, node, tag));
+                heap.push(Reverse((new_distance, to)));
+            }
+        }
+    }
+    records
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Customization {
+    pub(crate) metric: Vec<u32>,
+    /// Distances between the boundary nodes of every cell per level, row major,
+    /// `u32::MAX` if not reachable inside the cell
+    pub(crate) cliques: Vec<Vec<Vec<u32>>>,
+}
+
+impl Customization {
+    /// Edge weights of the metric, in the order of the CSR targets
+    pub fn metric(&self) -> &[u32] {
+        &self.metric
+    }
+
+    /// Distance inside the cell between the boundary nodes at positions `from` and `to`
+    pub fn clique_distance(
+        &self,
+        overlay: &Overlay,
+        level: usize,
+        cell: usize,
+        from: usize,
+        to: usize,
+    ) -> u32 {
+        let boundary_count = overlay.boundaries[level][cell].len();
+        self.cliques[level][cell][from * boundary_count + to]
+    }
+}
+
+/// Edges of `node` that stay inside its cell at `level`, given the cliques of the levels below
+pub(crate) fn cell_edges(
+    graph_csr: &impl CsrGraph,
+    overlay: &Overlay,
+    metric: &[u32],
+    cliques: &[Vec<Vec<u32>>],
+    level: usize,
+    node: usize,
+    edges: &mut Vec<TaggedEdge>,
+) {
+    let cell = overlay.cell(level, node);
+    let (offsets, targets) = (graph_csr.offsets(), graph_csr.targets());
+    let original_edges = (offsets[node] as usize..offsets[node + 1] as usize)
+        .map(|edge| (targets[edge] as usize, metric[edge]));
+
+    if level == 0 {
+        edges.extend(
+            original_edges
+                .filter(|&(to, _)| overlay.cell(0, to) == cell)
+                .map(|(to, distance)| (to, distance, 0)),
+        );
+        return;
+    }
+
+    // only boundary nodes of the level below take part
+    let below = level - 1;
+    let subcell = overlay.cell(below, node);
+    let Some(position) = overlay.position(below, node) else {
+        return;
+    };
+    let boundary = &overlay.boundaries[below][subcell];
+    let row = &cliques[below][subcell][position * boundary.len()..(position + 1) * boundary.len()];
+    edges.extend(
+        boundary
+            .iter()
+            .zip(row.iter())
+            .filter(|&(&to, &distance)| to as usize != node && distance != u32::MAX)
+            .map(|(&to, &distance)| (to as usize, distance, level)),
+    );
+    edges.extend(
+        original_edges
+            .filter(|&(to, _)| {
+                overlay.cell(below, to) != subcell && overlay.cell(level, to) == cell
+            })
+            .map(|(to, distance)| (to, distance, 0)),
+    );
+}
+
+/// Computes the cliques of all cells for `metric`, one weight per CSR edge.
+/// Returns `None` if the metric does not match the graph.
+pub fn customize(
+    graph_csr: &impl CsrGraph,
+    overlay: &Overlay,
+    metric: Vec<u32>,
+) -> Option<Customization> {
+    if metric.len() != graph_csr.edge_count() {
+        return None;
+    }
+
+    let mut cliques: Vec<Vec<Vec<u32>>> = Vec::with_capacity(overlay.level_count());
+    for level in 0..overlay.level_count() {
+        let mut level_cliques: Vec<Vec<u32>> = Vec::with_capacity(overlay.boundaries[level].len());
+        for boundary in overlay.boundaries[level].iter() {
+            let mut clique = vec![u32::MAX; boundary.len() * boundary.len()];
+            for (i, &from) in boundary.iter().enumerate() {
+                let records = local_search(from as usize, None, |node, edges| {
+                    cell_edges(graph_csr, overlay, &metric, &cliques, level, node, edges)
+                });
+                for (j, &to) in boundary.iter().enumerate() {
+                    if let Some(record) = records.get(&(to as usize)) {
+                        clique[i * boundary.len() + j] = record.0;
+                    }
+                }
+            }
+            level_cliques.push(clique);
+        }
+        cliques.push(level_cliques);
+    }
+
+    Some(Customization { metric, cliques })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crp::partition::partition;
+    use crate::dijkstra::graph_csr;
+
+    #[test]
+    fn customize_test() {
+        // square A-B-C-D with a long diagonal A-C, cells {A, B} and {C, D}
+        let csr = graph_csr::graph_matrix_to_csr(&vec![
+            vec![0, 1, 9, 1],
+            vec![1, 0, 1, 0],
+            vec![9, 1, 0, 1],
+            vec![1, 0, 1, 0],
+        ]);
+        let overlay = Overlay::new(&csr, partition(&csr, &[2]).unwrap()).unwrap();
+        assert_eq!(overlay.partition.cells[0], [0, 0, 1, 1]);
+
+        let customization = customize(&csr, &overlay, csr.weights.clone()).unwrap();
+        // all four nodes are boundary nodes, A-B stays 1 inside the cell
+        assert_eq!(customization.clique_distance(&overlay, 0, 0, 0, 1), 1);
+        assert_eq!(customization.clique_distance(&overlay, 0, 1, 1, 0), 1);
+
+        // a heavier metric only changes the cliques
+        let heavy: Vec<u32> = csr.weights.iter().map(|x| x * 3).collect();
+        let customization = customize(&csr, &overlay, heavy).unwrap();
+        assert_eq!(customization.clique_distance(&overlay, 0, 0, 1, 0), 3);
+
+        assert!(customize(&csr, &overlay, vec![1]).is_none());
+    }
+}
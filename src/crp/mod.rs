@@ -0,0 +1,3 @@
+pub mod partition;
+pub mod customization;
+pub mod query;
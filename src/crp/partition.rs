@@ -0,0 +1,211 @@
+// Multi-level partition and overlay topology for customizable route planning
+// Useful resources:
+// https://www.microsoft.com/en-us/research/publication/customizable-route-planning/
+//
+// Cells are grown breadth first up to a size limit, the coarsest level first, and every finer
+// cell is grown inside one cell of the level above, so the levels are nested.
+// A boundary node of a level has an edge, in either direction, to another cell of that level.
+// The partition and the boundaries do not depend on edge weights.
+
+use crate::graph_type::*;
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Partition {
+    /// Cell of every node per level, level 0 has the smallest cells
+    pub cells: Vec<Vec<u32>>,
+}
+
+impl Partition {
+    pub fn level_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn cell_count(&self, level: usize) -> usize {
+        self.cells[level]
+            .iter()
+            .max()
+            .map_or(0, |&x| x as usize + 1)
+    }
+}
+
+/// Splits the graph into nested cells, `cell_sizes` holds the largest cell size per level,
+/// from the finest to the coarsest. Returns `None` for empty or decreasing sizes.
+pub fn partition(graph_csr: &impl CsrGraph, cell_sizes: &[usize]) -> Option<Partition> {
+    if cell_sizes.first().is_none_or(|&x| x == 0) || cell_sizes.windows(2).any(|x| x[0] > x[1]) {
+        return None;
+    }
+
+    // regions grow along edges whatever their direction
+    let node_count = graph_csr.node_count();
+    let mut neighbours: Vec<Vec<usize>> = vec![Vec::new(); node_count];
+    for from in 0..node_count {
+        for (to, _) in graph_csr.edges(from) {
+            neighbours[from].push(to);
+            neighbours[to].push(from);
+        }
+    }
+    for list in neighbours.iter_mut() {
+        list.sort_unstable();
+        list.dedup();
+    }
+
+    let mut cells: Vec<Vec<u32>> = vec![Vec::new(); cell_sizes.len()];
+    let mut parent: Vec<u32> = vec![0; node_count];
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    for level in (0..cell_sizes.len()).rev() {
+        let mut cell = vec![u32::MAX; node_count];
+        let mut cell_count = 0;
+
+        for start in 0..node_count {
+            if cell[start] != u32::MAX {
+                continue;
+            }
+
+            cell[start] = cell_count;
+            let mut size = 1;
+            queue.push_back(start);
+            while let Some(node) = queue.pop_front() {
+                for &next in neighbours[node].iter() {
+                    if size == cell_sizes[level] {
+                        break;
+                    }
+                    if cell[next] == u32::MAX && parent[next] == parent[node] {
+                        cell[next] = cell_count;
+                        size += 1;
+                        queue.push_back(next);
+                    }
+                }
+            }
+            queue.clear();
+            cell_count += 1;
+        }
+
+        parent.clone_from(&cell);
+        cells[level] = cell;
+    }
+
+    Some(Partition { cells })
+}
+
+/// Boundary nodes of every cell, the metric independent part of the overlay
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overlay {
+    pub partition: Partition,
+    /// Boundary nodes of every cell per level, ascending
+    pub boundaries: Vec<Vec<Vec<u32>>>,
+    /// Position of each node in the boundary list of its cell per level, `u32::MAX` for inner nodes
+    positions: Vec<Vec<u32>>,
+}
+
+impl Overlay {
+    /// Returns `None` if the partition was made for another graph
+    pub fn new(graph_csr: &impl CsrGraph, partition: Partition) -> Option<Self> {
+        let node_count = graph_csr.node_count();
+        if partition.cells.iter().any(|x| x.len() != node_count) {
+            return None;
+        }
+
+        let mut boundaries: Vec<Vec<Vec<u32>>> = Vec::with_capacity(partition.level_count());
+        let mut positions: Vec<Vec<u32>> = Vec::with_capacity(partition.level_count());
+        for (level, cells) in partition.cells.iter().enumerate() {
+            let mut is_boundary = vec![false; node_count];
+            for from in 0..node_count {
+                for (to, _) in graph_csr.edges(from) {
+                    if cells[from] != cells[to] {
+                        is_boundary[from] = true;
+                        is_boundary[to] = true;
+                    }
+                }
+            }
+
+            let mut level_boundaries: Vec<Vec<u32>> = vec![Vec::new(); partition.cell_count(level)];
+            let mut level_positions = vec![u32::MAX; node_count];
+            for node in (0..node_count).filter(|&x| is_boundary[x]) {
+                let boundary = &mut level_boundaries[cells[node] as usize];
+                level_positions[node] = boundary.len() as u32;
+                boundary.push(node as u32);
+            }
+            boundaries.push(level_boundaries);
+            positions.push(level_positions);
+        }
+
+        Some(Overlay {
+            partition,
+            boundaries,
+            positions,
+        })
+    }
+
+    pub fn level_count(&self) -> usize {
+        self.partition.level_count()
+    }
+
+    pub fn cell(&self, level: usize, node: usize) -> usize {
+        self.partition.cells[level][node] as usize
+    }
+
+    /// Position of `node` in the boundary list of its cell, `None` for inner nodes
+    pub fn position(&self, level: usize, node: usize) -> Option<usize> {
+        match self.positions[level][node] {
+            u32::MAX => None,
+            position => Some(position as usize),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::graph_csr;
+    use crate::graph_samples;
+
+    #[test]
+    fn partition_test() {
+        let (graph, _) = graph_samples::sample1();
+        let (_, csr) = graph_csr::graph_map_to_csr(&graph);
+        let partition = partition(&csr, &[2, 4]).unwrap();
+        assert_eq!(partition.level_count(), 2);
+
+        for (level, max_size) in [(0, 2), (1, 4)] {
+            let mut sizes = vec![0; partition.cell_count(level)];
+            for &cell in partition.cells[level].iter() {
+                sizes[cell as usize] += 1;
+            }
+            assert!(sizes.iter().all(|&x| x > 0 && x <= max_size));
+        }
+
+        // nodes sharing a fine cell share the coarse cell too
+        for a in 0..csr.node_count() {
+            for b in 0..csr.node_count() {
+                if partition.cells[0][a] == partition.cells[0][b] {
+                    assert_eq!(partition.cells[1][a], partition.cells[1][b]);
+                }
+            }
+        }
+
+        assert!(super::partition(&csr, &[]).is_none());
+        assert!(super::partition(&csr, &[4, 2]).is_none());
+    }
+
+    #[test]
+    fn overlay_test() {
+        // path A-B-C-D cut into two cells
+        let csr = graph_csr::graph_matrix_to_csr(&vec![
+            vec![0, 1, 0, 0],
+            vec![1, 0, 1, 0],
+            vec![0, 1, 0, 1],
+            vec![0, 0, 1, 0],
+        ]);
+        let partition = partition(&csr, &[2]).unwrap();
+        assert_eq!(partition.cells[0], [0, 0, 1, 1]);
+
+        let overlay = Overlay::new(&csr, partition).unwrap();
+        assert_eq!(overlay.boundaries[0], [vec![1], vec![2]]);
+        assert_eq!(overlay.position(0, 2), Some(0));
+        assert_eq!(overlay.position(0, 0), None);
+
+        let other = graph_csr::graph_matrix_to_csr(&vec![vec![0]]);
+        assert!(Overlay::new(&other, overlay.partition).is_none());
+    }
+}
@@ -0,0 +1,262 @@
+// Shortest path queries over a customized overlay
+// The search runs on the original edges only inside the level 0 cells of the source and the target.
+// Any other node is seen at its query level, the first level where it shares a cell with
+// the source or the target: the search jumps over its cell one level below with the clique edges
+// and leaves that cell with the original edges.
+// Clique edges of the found path are unpacked with local searches inside their cells,
+// down to the original edges.
+
+use crate::crp::customization::*;
+use crate::crp::partition::Overlay;
+use crate::graph_type::*;
+use std::collections::HashMap;
+
+/// Edges of `node` seen by a query between `from` and `to`
+fn query_edges(
+    graph_csr: &impl CsrGraph,
+    overlay: &Overlay,
+    customization: &Customization,
+    from: usize,
+    to: usize,
+    node: usize,
+    edges: &mut Vec<TaggedEdge>,
+) {
+    let query_level = (0..overlay.level_count())
+        .find(|&level| {
+            let cell = overlay.cell(level, node);
+            cell == overlay.cell(level, from) || cell == overlay.cell(level, to)
+        })
+        .unwrap_or(overlay.level_count());
+    let (offsets, targets) = (graph_csr.offsets(), graph_csr.targets());
+    let original_edges = (offsets[node] as usize..offsets[node + 1] as usize)
+        .map(|edge| (targets[edge] as usize, customization.metric[edge]));
+
+    if query_level == 0 {
+        edges.extend(original_edges.map(|(to, distance)| (to, distance, 0)));
+        return;
+    }
+
+    let level = query_level - 1;
+    let cell = overlay.cell(level, node);
+    if let Some(position) = overlay.position(level, node) {
+        let boundary = &overlay.boundaries[level][cell];
+        let row = &customization.cliques[level][cell]
+            [position * boundary.len()..(position + 1) * boundary.len()];
+        edges.extend(
+            boundary
+                .iter()
+                .zip(row.iter())
+                .filter(|&(&to, &distance)| to as usize != node && distance != u32::MAX)
+                .map(|(&to, &distance)| (to as usize, distance, level + 1)),
+        );
+    }
+    edges.extend(
+        original_edges
+            .filter(|&(to, _)| overlay.cell(level, to) != cell)
+            .map(|(to, distance)| (to, distance, 0)),
+    );
+}
+
+/// Appends the original nodes of the edge `from` -> `to` to `path`, without `from`
+fn unpack_edge(
+    graph_csr: &impl CsrGraph,
+    overlay: &Overlay,
+    customization: &Customization,
+    (from, to, tag): (usize, usize, usize),
+    path: &mut Vec<usize>,
+) {
+    if tag == 0 {
+        path.push(to);
+        return;
+    }
+
+    let records = local_search(from, Some(to), |node, edges| {
+        cell_edges(
+            graph_csr,
+            overlay,
+            &customization.metric,
+            &customization.cliques,
+            tag - 1,
+            node,
+            edges,
+        )
+    });
+    for edge in trace_edges(&records, from, to) {
+        unpack_edge(graph_csr, overlay, customization, edge, path);
+    }
+}
+
+/// Edges `(from, to, tag)` of the search path from `source` to `target`
+fn trace_edges(
+    records: &HashMap<usize, SearchRecord>,
+    source: usize,
+    target: usize,
+) -> Vec<(usize, usize, usize)> {
+    let mut edges: Vec<(usize, usize, usize)> = Vec::new();
+    let mut node = target;
+    while node != source {
+        let (_, previous, tag) = records[&node];
+        edges.push((previous, node, tag));
+        node = previous;
+    }
+    edges.reverse();
+    edges
+}
+
+/// Shortest path with the metric of `customization`, `None` if a node is unknown or not reachable
+pub fn shortest_path(
+    graph_csr: &impl CsrGraph,
+    overlay: &Overlay,
+    customization: &Customization,
+    from: usize,
+    to: usize,
+) -> Option<ShortestPath> {
+    let node_count = graph_csr.node_count();
+    if from >= node_count || to >= node_count {
+        return None;
+    }
+    if from == to {
+        return Some(ShortestPath {
+            from,
+            to,
+            distance: 0,
+            path: vec![from, to],
+        });
+    }
+
+    let records = local_search(from, Some(to), |node, edges| {
+        query_edges(graph_csr, overlay, customization, from, to, node, edges)
+    });
+    let &(distance, _, _) = records.get(&to)?;
+    if distance == u32::MAX {
+        return None;
+    }
+
+    let mut path: Vec<usize> = vec![from];
+    for edge in trace_edges(&records, from, to) {
+        unpack_edge(graph_csr, overlay, customization, edge, &mut path);
+    }
+
+    Some(ShortestPath {
+        from,
+        to,
+        distance,
+        path,
+    })
+}
+
+/// Shortest path between named nodes of a CSR graph
+pub fn shortest_path_named(
+    graph: &GraphCsrNamed,
+    overlay: &Overlay,
+    customization: &Customization,
+    from: &str,
+    to: &str,
+) -> Option<ShortestPathNamed> {
+    let from = graph.names.id(from)?;
+    let to = graph.names.id(to)?;
+    let shortest_path =
+        shortest_path(&graph.csr, overlay, customization, from.index(), to.index())?;
+    Some(graph.names.resolve_path(&shortest_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crp::partition::partition;
+    use crate::dijkstra::graph_csr;
+    use crate::dijkstra::utils::build_shortest_path_from_tree;
+    use crate::graph_samples;
+    use crate::random_graphs::random_graph_csr;
+
+    fn assert_valid_path(graph_csr: &GraphCsr, metric: &[u32], shortest_path: &ShortestPath) {
+        if shortest_path.from == shortest_path.to {
+            assert_eq!(shortest_path.path, [shortest_path.from, shortest_path.to]);
+            return;
+        }
+        let mut distance = 0;
+        for pair in shortest_path.path.windows(2) {
+            let edge = (graph_csr.offsets[pair[0]]..graph_csr.offsets[pair[0] + 1])
+                .find(|&edge| graph_csr.targets[edge as usize] as usize == pair[1])
+                .unwrap();
+            distance += metric[edge as usize];
+        }
+        assert_eq!(shortest_path.path[0], shortest_path.from);
+        assert_eq!(shortest_path.path.last(), Some(&shortest_path.to));
+        assert_eq!(distance, shortest_path.distance);
+    }
+
+    #[test]
+    fn crp_samples_test() {
+        for (graph, expected_shortest_paths) in [
+            graph_samples::sample1(),
+            graph_samples::sample2(),
+            graph_samples::sample3(),
+            graph_samples::sample4(),
+        ] {
+            let graph_csr_named = graph_csr::graph_map_to_csr_named(&graph);
+            let csr = &graph_csr_named.csr;
+            let overlay = Overlay::new(csr, partition(csr, &[2, 4]).unwrap()).unwrap();
+            let customization = customize(csr, &overlay, csr.weights.clone()).unwrap();
+
+            for expected in expected_shortest_paths {
+                let shortest_path = shortest_path_named(
+                    &graph_csr_named,
+                    &overlay,
+                    &customization,
+                    &expected.from,
+                    &expected.to,
+                )
+                .unwrap();
+                assert_eq!(shortest_path, expected);
+            }
+
+            // every pair, unpacked hop by hop
+            for from in 0..csr.node_count() {
+                let tree = graph_csr::shortest_path_tree(csr, from).unwrap();
+                for node in tree.iter() {
+                    let shortest_path =
+                        shortest_path(csr, &overlay, &customization, from, node.to).unwrap();
+                    assert_eq!(shortest_path.distance, node.distance);
+                    assert_valid_path(csr, &csr.weights, &shortest_path);
+                }
+            }
+            assert!(
+                shortest_path_named(&graph_csr_named, &overlay, &customization, "A", "X").is_none()
+            );
+        }
+    }
+
+    #[test]
+    fn crp_random_test() {
+        let mut seed = 5;
+        for round in 0..10 {
+            let csr = random_graph_csr(60, 3 + round % 5, 20, &mut seed);
+            let overlay = Overlay::new(&csr, partition(&csr, &[4, 12, 30]).unwrap()).unwrap();
+
+            // the same overlay serves a second metric after a new customization
+            let truck: Vec<u32> = csr.weights.iter().map(|x| x * 2 + x % 7).collect();
+            let truck_csr = GraphCsr {
+                weights: truck.clone(),
+                ..csr.clone()
+            };
+            for (graph_csr, metric) in [(&csr, csr.weights.clone()), (&truck_csr, truck)] {
+                let customization = customize(&csr, &overlay, metric.clone()).unwrap();
+                for from in (0..60).step_by(7) {
+                    let tree = graph_csr::shortest_path_tree(graph_csr, from).unwrap();
+                    for to in 0..60 {
+                        let shortest_path = shortest_path(&csr, &overlay, &customization, from, to);
+                        match build_shortest_path_from_tree(to, &tree) {
+                            Some(expected) => {
+                                let shortest_path = shortest_path.unwrap();
+                                assert_eq!(shortest_path.distance, expected.distance);
+                                assert_valid_path(&csr, &metric, &shortest_path);
+                            }
+                            None => assert!(shortest_path.is_none()),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,328 @@
+// Alternative routes using the penalty method
+// The shortest path is searched repeatedly, after each search the edges of the found path
+// get heavier, which pushes the next search towards different roads. A candidate is kept
+// if it is not much longer than the optimum and shares little length with the kept routes.
+// Every candidate is penalized, kept or not, so a rejected route is not found over and over.
+// Penalized weights are kept in u64, repeated penalties would overflow u32.
+
+use crate::dijkstra::graph_matrix;
+use crate::graph_type::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+#[derive(Debug)]
+pub struct AlternativeOptions {
+    /// Number of alternatives to return besides the shortest path
+    pub count: usize,
+    /// Maximum length of an alternative relative to the shortest path, e.g. 1.3 for +30%
+    pub max_stretch: f64,
+    /// Maximum shared length between two routes, relative to the shorter one
+    pub max_shared: f64,
+    /// Factor applied to the edges of each found path
+    pub penalty: f64,
+    /// Upper limit of searches
+    pub max_iterations: usize,
+}
+
+impl Default for AlternativeOptions {
+    fn default() -> Self {
+        AlternativeOptions {
+            count: 3,
+            max_stretch: 1.3,
+            max_shared: 0.6,
+            penalty: 1.5,
+            max_iterations: 20,
+        }
+    }
+}
+
+fn path_edges(path: &[usize]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    path.windows(2)
+        .map(|pair| (pair[0].min(pair[1]), pair[0].max(pair[1])))
+}
+
+/// Length of `path` on the original weights, `None` if it does not fit below `u32::MAX`
+fn path_distance(graph_matrix: &GraphMatrix, path: &[usize]) -> Option<u32> {
+    let distance: u64 = path_edges(path)
+        .map(|(x, y)| graph_matrix[x][y] as u64)
+        .sum();
+    u32::try_from(distance).ok().filter(|&x| x != u32::MAX)
+}
+
+fn penalize(penalized: &mut [Vec<u64>], path: &[usize], penalty: f64) {
+    for (x, y) in path_edges(path) {
+        // float to integer casts saturate
+        let weight = (penalized[x][y] as f64 * penalty).ceil() as u64;
+        penalized[x][y] = weight;
+        penalized[y][x] = weight;
+    }
+}
+
+/// Dijkstra path on the penalized weights, nodes are settled by (distance, index)
+/// as in `graph_matrix::shortest_path_tree`, so ties are broken the same way
+fn penalized_path(penalized: &[Vec<u64>], from: usize, to: usize) -> Option<Vec<usize>> {
+    let node_count = penalized.len();
+    let mut distances = vec![u64::MAX; node_count];
+    let mut previous: Vec<usize> = (0..node_count).collect();
+    let mut settled = vec![false; node_count];
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = BinaryHeap::from([Reverse((0, from))]);
+    distances[from] = 0;
+
+    while let Some(Reverse((distance, node))) = heap.pop() {
+        if settled[node] {
+            continue;
+        }
+        if node == to {
+            break;
+        }
+        settled[node] = true;
+
+        for (next, &weight) in penalized[node].iter().enumerate() {
+            if weight == 0 || settled[next] {
+                continue;
+            }
+            let new_distance = distance.saturating_add(weight);
+            if new_distance < distances[next] {
+                distances[next] = new_distance;
+                previous[next] = node;
+                heap.push(Reverse((new_distance, next)));
+            }
+        }
+    }
+    if distances[to] == u64::MAX {
+        return None;
+    }
+
+    let mut path: Vec<usize> = vec![to];
+    let mut node = to;
+    while node != from {
+        node = previous[node];
+        path.push(node);
+    }
+    path.reverse();
+    Some(path)
+}
+
+fn shared_ratio(graph_matrix: &GraphMatrix, a: &ShortestPath, b: &ShortestPath) -> f64 {
+    let b_edges: Vec<(usize, usize)> = path_edges(&b.path).collect();
+    let shared: u64 = path_edges(&a.path)
+        .filter(|edge| b_edges.contains(edge))
+        .map(|(x, y)| graph_matrix[x][y] as u64)
+        .sum();
+    shared as f64 / a.distance.min(b.distance).max(1) as f64
+}
+
+/// Shortest path first, then up to `options.count` alternatives ordered by length
+pub fn alternative_paths(
+    graph_matrix: &GraphMatrix,
+    from: usize,
+    to: usize,
+    options: &AlternativeOptions,
+) -> Option<Vec<ShortestPath>> {
+    let node_count = graph_matrix.len();
+    if from >= node_count || to >= node_count {
+        return None;
+    }
+    if from == to {
+        return Some(vec![ShortestPath {
+            from,
+            to,
+            distance: 0,
+            path: vec![from, to],
+        }]);
+    }
+
+    let mut penalized: Vec<Vec<u64>> = graph_matrix
+        .iter()
+        .map(|row| row.iter().map(|&x| x as u64).collect())
+        .collect();
+    let path = penalized_path(&penalized, from, to)?;
+    let best = ShortestPath {
+        from,
+        to,
+        distance: path_distance(graph_matrix, &path)?,
+        path,
+    };
+    penalize(&mut penalized, &best.path, options.penalty);
+
+    let max_distance = (best.distance as f64 * options.max_stretch) as u64;
+    let mut paths: Vec<ShortestPath> = vec![best];
+
+    for _ in 0..options.max_iterations {
+        if paths.len() > options.count {
+            break;
+        }
+
+        // weights only grow, so the target stays reachable
+        let path = penalized_path(&penalized, from, to)?;
+        penalize(&mut penalized, &path, options.penalty);
+
+        // measuring the candidate on the original weights
+        let Some(distance) = path_distance(graph_matrix, &path) else {
+            continue;
+        };
+        if distance as u64 > max_distance {
+            continue;
+        }
+        let candidate = ShortestPath {
+            from,
+            to,
+            distance,
+            path,
+        };
+        if paths
+            .iter()
+            .any(|path| shared_ratio(graph_matrix, &candidate, path) > options.max_shared)
+        {
+            continue;
+        }
+        paths.push(candidate);
+    }
+
+    paths[1..].sort_by_key(|x| x.distance);
+    Some(paths)
+}
+
+/// Named version of [`alternative_paths`] for map based graphs
+pub fn alternative_paths_named(
+    graph_map: &GraphMap,
+    from: &str,
+    to: &str,
+    options: &AlternativeOptions,
+) -> Option<Vec<ShortestPathNamed>> {
+    let graph = graph_matrix::graph_map_to_matrix_named(graph_map);
+    let paths = alternative_paths(
+        &graph.matrix,
+        graph.names.id(from)?.index(),
+        graph.names.id(to)?.index(),
+        options,
+    )?;
+
+    Some(paths.iter().map(|x| graph.names.resolve_path(x)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::graph_map;
+    use crate::graph_samples;
+
+    #[test]
+    fn alternative_paths_test() {
+        let (graph, _) = graph_samples::sample1();
+
+        let options = AlternativeOptions {
+            max_stretch: 1.5,
+            ..Default::default()
+        };
+        let paths = alternative_paths_named(&graph, "A", "E", &options).unwrap();
+        assert!(paths.len() > 1);
+        assert_eq!(
+            paths[0],
+            graph_map::shortest_path(&graph, "A", "E").unwrap()
+        );
+
+        let GraphMatrixNamed { names, matrix } = graph_matrix::graph_map_to_matrix_named(&graph);
+        let (a, e) = (names.id("A").unwrap(), names.id("E").unwrap());
+        let numbered: Vec<ShortestPath> =
+            alternative_paths(&matrix, a.index(), e.index(), &options).unwrap();
+        for (i, path) in numbered.iter().enumerate() {
+            assert!(path.distance as f64 <= paths[0].distance as f64 * 1.5);
+            assert_eq!(
+                path.distance,
+                path.path
+                    .windows(2)
+                    .map(|x| matrix[x[0]][x[1]])
+                    .sum::<u32>()
+            );
+            for other in numbered[..i].iter() {
+                assert!(shared_ratio(&matrix, path, other) <= options.max_shared);
+            }
+        }
+    }
+
+    #[test]
+    fn alternative_paths_large_weights_test() {
+        // square A-B-C-D, both ways around take 2e9, repeated penalties go past u32::MAX
+        let graph = graph_samples::build_undirected_graph(&[
+            ("A", "B", 1_000_000_000),
+            ("B", "C", 1_000_000_000),
+            ("C", "D", 1_000_000_000),
+            ("D", "A", 1_000_000_000),
+        ]);
+        let options = AlternativeOptions {
+            max_shared: 0.0,
+            penalty: 3.0,
+            ..Default::default()
+        };
+        let paths = alternative_paths_named(&graph, "A", "C", &options).unwrap();
+        let distances: Vec<u32> = paths.iter().map(|x| x.distance).collect();
+        assert_eq!(distances, [2_000_000_000, 2_000_000_000]);
+
+        // paths longer than u32 can hold are not reachable
+        let graph = graph_samples::build_undirected_graph(&[
+            ("A", "B", 3_000_000_000),
+            ("B", "C", 3_000_000_000),
+        ]);
+        assert!(alternative_paths_named(&graph, "A", "C", &options).is_none());
+    }
+
+    #[test]
+    fn alternative_paths_rejected_penalty_test() {
+        // best A-B-C-D (21), A-B-X-D (23) shares A-B and is rejected, A-Y-D (26) is disjoint
+        let graph = graph_samples::build_undirected_graph(&[
+            ("A", "B", 1),
+            ("B", "C", 10),
+            ("C", "D", 10),
+            ("B", "X", 10),
+            ("X", "D", 12),
+            ("A", "Y", 13),
+            ("Y", "D", 13),
+        ]);
+        // the rejected route is penalized too, so the second search already moves on
+        let options = AlternativeOptions {
+            count: 1,
+            max_shared: 0.01,
+            penalty: 2.0,
+            max_iterations: 2,
+            ..Default::default()
+        };
+        let paths = alternative_paths_named(&graph, "A", "D", &options).unwrap();
+        let found: Vec<Vec<String>> = paths.into_iter().map(|x| x.path).collect();
+        assert_eq!(found, [vec!["A", "B", "C", "D"], vec!["A", "Y", "D"]]);
+    }
+
+    #[test]
+    fn alternative_paths_limits_test() {
+        let (graph, _) = graph_samples::sample1();
+
+        // no stretch allowed, only the shortest path qualifies
+        let options = AlternativeOptions {
+            max_stretch: 1.0,
+            ..Default::default()
+        };
+        let paths = alternative_paths_named(&graph, "A", "E", &options).unwrap();
+        assert_eq!(paths.len(), 1);
+
+        let options = AlternativeOptions {
+            count: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            alternative_paths_named(&graph, "A", "E", &options)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        assert_eq!(
+            alternative_paths_named(&graph, "A", "A", &AlternativeOptions::default())
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(
+            alternative_paths_named(&graph, "A", "X", &AlternativeOptions::default()).is_none()
+        );
+    }
+}
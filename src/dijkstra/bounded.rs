@@ -0,0 +1,132 @@
+// Distance-bounded search and isochrones
+// The search settles nodes in distance order and stops as soon as the frontier passes the budget,
+// so only the part of the graph around the source is explored.
+
+use crate::graph_type::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Shortest path tree restricted to the nodes within `budget` of `source`, sorted by node name
+pub fn bounded_shortest_path_tree(
+    graph_map: &GraphMap,
+    source: &str,
+    budget: u32,
+) -> Option<ShortestPathTreeNamed> {
+    if !graph_map.contains_key(source) {
+        return None;
+    }
+
+    // settled nodes with their distance and previous node
+    let mut settled: HashMap<&str, (u32, &str)> = HashMap::new();
+    let mut heap: BinaryHeap<Reverse<(u32, &str, &str)>> = BinaryHeap::new();
+    heap.push(Reverse((0, source, source)));
+
+    while let Some(Reverse((distance, node, previous))) = heap.pop() {
+        if distance > budget {
+            break;
+        }
+        if settled.contains_key(node) {
+            continue;
+        }
+        settled.insert(node, (distance, previous));
+
+        if let Some(edges) = graph_map.get(node) {
+            for (next, edge_distance) in edges {
+                let next_distance = distance.saturating_add(*edge_distance);
+                if next_distance <= budget && !settled.contains_key(next.as_str()) {
+                    heap.push(Reverse((next_distance, next, node)));
+                }
+            }
+        }
+    }
+
+    let mut tree: ShortestPathTreeNamed = settled
+        .into_iter()
+        .map(|(node, (distance, previous))| ShortestPathTreeNodeNamed {
+            from: source.to_string(),
+            to: node.to_string(),
+            distance,
+            previous: previous.to_string(),
+        })
+        .collect();
+    tree.sort_by(|a, b| a.to.cmp(&b.to));
+
+    Some(tree)
+}
+
+/// Labels every node within the largest budget with the smallest budget that covers it,
+/// e.g. budgets 5, 10 and 15 give three catchment bands from a single search.
+pub fn isochrones(
+    graph_map: &GraphMap,
+    source: &str,
+    budgets: &[u32],
+) -> Option<Vec<IsochroneNodeNamed>> {
+    let mut bands = budgets.to_vec();
+    bands.sort();
+    let max_budget = *bands.last()?;
+
+    let tree = bounded_shortest_path_tree(graph_map, source, max_budget)?;
+    Some(
+        tree.into_iter()
+            .map(|node| IsochroneNodeNamed {
+                band: *bands.iter().find(|&&x| node.distance <= x).unwrap(),
+                to: node.to,
+                distance: node.distance,
+                previous: node.previous,
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::graph_map;
+    use crate::dijkstra::utils::*;
+    use crate::graph_samples;
+
+    #[test]
+    fn bounded_shortest_path_tree_test() {
+        let (graph, _) = graph_samples::sample1();
+
+        let tree = bounded_shortest_path_tree(&graph, "A", 11).unwrap();
+        let reached: Vec<(&str, u32)> = tree.iter().map(|x| (x.to.as_str(), x.distance)).collect();
+        assert_eq!(reached, [("A", 0), ("B", 4), ("F", 11), ("G", 9), ("H", 8)]);
+
+        let shortest_path = build_shortest_path_from_tree_named("F", &tree).unwrap();
+        assert_eq!(shortest_path.path, ["A", "H", "G", "F"]);
+
+        // an unlimited budget gives the full tree
+        let full_tree = bounded_shortest_path_tree(&graph, "A", u32::MAX).unwrap();
+        let expected_tree = graph_map::shortest_path_tree(&graph, "A").unwrap();
+        for (node, expected) in full_tree.iter().zip(expected_tree.iter()) {
+            assert_eq!(node.to, expected.to);
+            assert_eq!(node.distance, expected.distance);
+        }
+
+        assert_eq!(bounded_shortest_path_tree(&graph, "A", 0).unwrap().len(), 1);
+        assert!(bounded_shortest_path_tree(&graph, "X", 10).is_none());
+    }
+
+    #[test]
+    fn isochrones_test() {
+        let (graph, _) = graph_samples::sample1();
+
+        let nodes = isochrones(&graph, "A", &[15, 5, 10]).unwrap();
+        let bands: Vec<(&str, u32)> = nodes.iter().map(|x| (x.to.as_str(), x.band)).collect();
+        assert_eq!(
+            bands,
+            [
+                ("A", 5),
+                ("B", 5),
+                ("C", 15),
+                ("F", 15),
+                ("G", 10),
+                ("H", 10),
+                ("I", 15)
+            ]
+        );
+
+        assert!(isochrones(&graph, "A", &[]).is_none());
+    }
+}
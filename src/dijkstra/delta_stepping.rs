@@ -0,0 +1,354 @@
+// Delta-stepping single source shortest paths (Meyer and Sanders)
+// Useful resources:
+// https://en.wikipedia.org/wiki/Parallel_single-source_shortest_path_algorithm#Delta_stepping_algorithm
+//
+// Nodes are kept in buckets of width delta by tentative distance, the lowest bucket is processed in phases:
+// 1. light edges (weight <= delta) of the bucket nodes are relaxed until the bucket stays empty,
+//    nodes may come back to the same bucket
+// 2. heavy edges of every node removed from the bucket are relaxed once
+// The relaxation requests of a phase are generated by worker threads, started once for the whole run,
+// and applied afterwards. Edges keep their direction, as in `graph_csr`.
+// Requests that tie with the current distance are applied as well, so the previous node is chosen
+// during relaxation with the tie-breaking of `graph_csr::shortest_path_tree` and both give the same tree.
+
+use crate::dijkstra::graph_csr;
+use crate::graph_type::*;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+// below this many frontier nodes the requests are generated on the calling thread
+const PARALLEL_THRESHOLD: usize = 256;
+
+#[derive(Debug, Default)]
+pub struct DeltaSteppingOptions {
+    /// Bucket width, the average edge weight by default
+    pub delta: Option<u32>,
+    /// Number of worker threads, all available cores by default
+    pub threads: Option<usize>,
+}
+
+/// Relaxation request `(to, distance, from)`
+type Request = (usize, u32, usize);
+
+/// Light or heavy edges of every node, in CSR layout
+struct EdgeSet {
+    offsets: Vec<u32>,
+    targets: Vec<u32>,
+    weights: Vec<u32>,
+}
+
+impl EdgeSet {
+    fn new(graph_csr: &impl CsrGraph, keep: impl Fn(u32) -> bool) -> Self {
+        let mut edges = EdgeSet {
+            offsets: Vec::with_capacity(graph_csr.node_count() + 1),
+            targets: Vec::new(),
+            weights: Vec::new(),
+        };
+        edges.offsets.push(0);
+        for node in 0..graph_csr.node_count() {
+            for (to, weight) in graph_csr.edges(node).filter(|x| keep(x.1)) {
+                edges.targets.push(to as u32);
+                edges.weights.push(weight);
+            }
+            edges.offsets.push(edges.targets.len() as u32);
+        }
+        edges
+    }
+
+    /// Requests of the edges leaving `nodes` that do not make a distance longer
+    fn requests(&self, nodes: &[usize], distances: &[AtomicU32]) -> Vec<Request> {
+        let mut requests: Vec<Request> = Vec::new();
+        for &node in nodes {
+            let distance = distances[node].load(Ordering::Relaxed);
+            let range = self.offsets[node] as usize..self.offsets[node + 1] as usize;
+            for (&to, &weight) in self.targets[range.clone()]
+                .iter()
+                .zip(self.weights[range].iter())
+            {
+                let to = to as usize;
+                let new_distance = distance.saturating_add(weight);
+                if new_distance != u32::MAX && new_distance <= distances[to].load(Ordering::Relaxed)
+                {
+                    requests.push((to, new_distance, node));
+                }
+            }
+        }
+        requests
+    }
+}
+
+/// Worker threads kept for the whole run, each phase hands them a share of the frontier
+struct WorkerPool<'a> {
+    jobs: Vec<Sender<(&'a EdgeSet, Vec<usize>)>>,
+    results: Receiver<Vec<Request>>,
+    distances: &'a [AtomicU32],
+}
+
+impl<'a> WorkerPool<'a> {
+    /// Runs `run` with `threads - 1` workers next to the calling thread
+    fn scope<T>(
+        threads: usize,
+        distances: &'a [AtomicU32],
+        run: impl FnOnce(&WorkerPool<'a>) -> T,
+    ) -> T {
+        thread::scope(|scope| {
+            let (result_sender, results) = mpsc::channel();
+            let mut jobs: Vec<Sender<(&'a EdgeSet, Vec<usize>)>> = Vec::new();
+            for _ in 1..threads {
+                let (job_sender, job_receiver) = mpsc::channel::<(&'a EdgeSet, Vec<usize>)>();
+                let result_sender = result_sender.clone();
+                scope.spawn(move || {
+                    for (edges, nodes) in job_receiver {
+                        if result_sender
+                            .send(edges.requests(&nodes, distances))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                });
+                jobs.push(job_sender);
+            }
+
+            // the workers stop once the job senders are dropped with the pool
+            let pool = WorkerPool {
+                jobs,
+                results,
+                distances,
+            };
+            run(&pool)
+        })
+    }
+
+    fn requests(&self, edges: &'a EdgeSet, frontier: &[usize]) -> Vec<Request> {
+        if self.jobs.is_empty() || frontier.len() < PARALLEL_THRESHOLD {
+            return edges.requests(frontier, self.distances);
+        }
+
+        let chunk_size = frontier.len().div_ceil(self.jobs.len() + 1);
+        let mut chunks = frontier.chunks(chunk_size);
+        let own = chunks.next().unwrap();
+        let mut sent = 0;
+        for (job, chunk) in self.jobs.iter().zip(chunks) {
+            job.send((edges, chunk.to_vec())).unwrap();
+            sent += 1;
+        }
+
+        let mut requests = edges.requests(own, self.distances);
+        for _ in 0..sent {
+            requests.extend(self.results.recv().unwrap());
+        }
+        requests
+    }
+}
+
+struct Buckets {
+    delta: u32,
+    // buckets may hold stale entries, a node belongs to the bucket of its current distance
+    nodes: BTreeMap<u32, Vec<usize>>,
+}
+
+/// Applies the requests of a phase: shorter distances move the node to its new bucket,
+/// ties keep the previous node that Dijkstra settles first, by (distance, index)
+fn apply_requests(
+    requests: Vec<Request>,
+    source: usize,
+    distances: &[AtomicU32],
+    previous: &mut [usize],
+    buckets: &mut Buckets,
+) {
+    let order = |node: usize| (distances[node].load(Ordering::Relaxed), node);
+    for (to, distance, from) in requests {
+        if to == source {
+            continue;
+        }
+        let current = distances[to].load(Ordering::Relaxed);
+        if distance < current {
+            distances[to].store(distance, Ordering::Relaxed);
+            previous[to] = from;
+            buckets
+                .nodes
+                .entry(distance / buckets.delta)
+                .or_default()
+                .push(to);
+        } else if distance == current && order(from) < order(previous[to]) {
+            previous[to] = from;
+        }
+    }
+}
+
+/// Shortest path tree from `source`, equal to `graph_csr::shortest_path_tree`
+pub fn shortest_path_tree(
+    graph_csr: &impl CsrGraph,
+    source: usize,
+    options: &DeltaSteppingOptions,
+) -> Option<ShortestPathTree> {
+    let node_count = graph_csr.node_count();
+    if source >= node_count {
+        return None;
+    }
+
+    // splitting the edges into light and heavy ones
+    let sum: u64 = graph_csr.weights().iter().map(|&x| x as u64).sum();
+    let average = sum / (graph_csr.edge_count() as u64).max(1);
+    let delta = options.delta.unwrap_or(average as u32).max(1);
+    let threads = options
+        .threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |x| x.get()))
+        .max(1);
+    let light = EdgeSet::new(graph_csr, |weight| weight <= delta);
+    let heavy = EdgeSet::new(graph_csr, |weight| weight > delta);
+
+    let distances: Vec<AtomicU32> = (0..node_count).map(|_| AtomicU32::new(u32::MAX)).collect();
+    let mut previous: Vec<usize> = (0..node_count).collect();
+    let mut buckets = Buckets {
+        delta,
+        nodes: BTreeMap::from([(0, vec![source])]),
+    };
+    distances[source].store(0, Ordering::Relaxed);
+
+    WorkerPool::scope(threads, &distances, |pool| {
+        let mut in_frontier = vec![false; node_count];
+        while let Some((&bucket, _)) = buckets.nodes.first_key_value() {
+            let mut removed: Vec<usize> = Vec::new();
+
+            // light edge phases until no node falls back into the bucket
+            while let Some(nodes) = buckets.nodes.remove(&bucket) {
+                let mut frontier: Vec<usize> = Vec::with_capacity(nodes.len());
+                for node in nodes {
+                    if distances[node].load(Ordering::Relaxed) / delta == bucket
+                        && !in_frontier[node]
+                    {
+                        in_frontier[node] = true;
+                        frontier.push(node);
+                    }
+                }
+                for &node in frontier.iter() {
+                    in_frontier[node] = false;
+                }
+
+                let requests = pool.requests(&light, &frontier);
+                apply_requests(requests, source, &distances, &mut previous, &mut buckets);
+                removed.extend(frontier);
+            }
+
+            removed.sort_unstable();
+            removed.dedup();
+            let requests = pool.requests(&heavy, &removed);
+            apply_requests(requests, source, &distances, &mut previous, &mut buckets);
+        }
+    });
+
+    Some(
+        distances
+            .into_iter()
+            .enumerate()
+            .map(|(node, distance)| ShortestPathTreeNode {
+                from: source,
+                to: node,
+                distance: distance.into_inner(),
+                previous: previous[node],
+            })
+            .collect(),
+    )
+}
+
+/// Named version of [`shortest_path_tree`] for map based graphs, run on their CSR form
+pub fn shortest_path_tree_named(
+    graph_map: &GraphMap,
+    source: &str,
+    options: &DeltaSteppingOptions,
+) -> Option<ShortestPathTreeNamed> {
+    let graph = graph_csr::graph_map_to_csr_named(graph_map);
+    let tree = shortest_path_tree(&graph.csr, graph.names.id(source)?.index(), options)?;
+    Some(graph.names.resolve_tree(&tree))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::graph_map;
+    use crate::graph_samples;
+    use crate::random_graphs::*;
+
+    #[test]
+    fn delta_stepping_samples_test() {
+        for (graph, expected_shortest_paths) in [
+            graph_samples::sample1(),
+            graph_samples::sample2(),
+            graph_samples::sample3(),
+            graph_samples::sample4(),
+        ] {
+            let source = &expected_shortest_paths[0].from;
+            for delta in [None, Some(1), Some(100)] {
+                let options = DeltaSteppingOptions {
+                    delta,
+                    threads: Some(2),
+                };
+                assert_eq!(
+                    shortest_path_tree_named(&graph, source, &options),
+                    graph_map::shortest_path_tree(&graph, source)
+                );
+            }
+        }
+        assert!(
+            shortest_path_tree_named(&graph_samples::sample1().0, "X", &Default::default())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn delta_stepping_random_test() {
+        let mut seed = 3;
+        for round in 0..30 {
+            // few heavy weights on small graphs, many threads on the large ones
+            let (node_count, threads) = if round % 5 == 0 { (400, 4) } else { (40, 2) };
+            let graph_csr = random_graph_csr(node_count, 5 + round % 10, 20, &mut seed);
+            let source = round as usize % node_count;
+            let expected = graph_csr::shortest_path_tree(&graph_csr, source).unwrap();
+
+            for delta in [None, Some(1), Some(4), Some(50)] {
+                let options = DeltaSteppingOptions {
+                    delta,
+                    threads: Some(threads),
+                };
+                assert_eq!(
+                    shortest_path_tree(&graph_csr, source, &options).unwrap(),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn worker_pool_requests_test() {
+        let mut seed = 11;
+        let node_count = PARALLEL_THRESHOLD * 2;
+        let graph_csr = random_graph_csr(node_count, 2, 9, &mut seed);
+        let edges = EdgeSet::new(&graph_csr, |_| true);
+        let distances: Vec<AtomicU32> = (0..node_count)
+            .map(|node| {
+                AtomicU32::new(if node < node_count / 2 {
+                    node as u32
+                } else {
+                    u32::MAX
+                })
+            })
+            .collect();
+
+        // the frontier is split between the workers, the requests stay the same
+        let frontier: Vec<usize> = (0..node_count / 2).collect();
+        let mut expected = edges.requests(&frontier, &distances);
+        let mut requests = WorkerPool::scope(4, &distances, |pool| {
+            // the same workers serve several phases
+            pool.requests(&edges, &frontier);
+            pool.requests(&edges, &frontier)
+        });
+        expected.sort();
+        requests.sort();
+        assert!(!expected.is_empty());
+        assert_eq!(requests, expected);
+    }
+}
@@ -0,0 +1,380 @@
+// Incremental repair of a shortest path tree after edge changes (dynamic SSSP)
+// Useful resources:
+// https://en.wikipedia.org/wiki/Dynamic_problem_(algorithms)
+//
+// In the spirit of Ramalingam and Reps, only the part of the tree touched by a batch is searched:
+// 1. the subtrees hanging below increased or removed tree edges lose their distances,
+//    each of their nodes gets a tentative distance from the neighbours outside the subtrees
+// 2. decreased or inserted edges seed their targets with the shorter distance
+// 3. a heap based search from the seeded nodes settles the new distances
+// 4. previous nodes are chosen again around the changed nodes,
+//    using the same tie-breaking as `graph_matrix::shortest_path_tree`
+
+use crate::graph_type::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Previous node chosen by `graph_matrix::shortest_path_tree`: nodes are settled by
+/// (distance, index), so the first tight predecessor in that order wins
+fn canonical_previous(graph_matrix: &GraphMatrix, distances: &[u32], node: usize) -> usize {
+    if distances[node] == u32::MAX {
+        return node;
+    }
+
+    let mut previous = node;
+    for (from, row) in graph_matrix.iter().enumerate() {
+        let weight = row[node];
+        if weight == 0 || distances[from] == u32::MAX {
+            continue;
+        }
+        if distances[from].saturating_add(weight) == distances[node]
+            && (previous == node || distances[from] < distances[previous])
+        {
+            previous = from;
+        }
+    }
+    previous
+}
+
+/// Applies `updates` to `graph_matrix`, an edge listed twice keeps its last distance.
+/// Returns the weight each update replaced, for [`repair_shortest_path_tree`].
+pub fn apply_edge_updates(
+    graph_matrix: &mut GraphMatrix,
+    updates: &[EdgeUpdate],
+) -> Option<Vec<u32>> {
+    let node_count = graph_matrix.len();
+    if updates
+        .iter()
+        .any(|x| x.from >= node_count || x.to >= node_count || x.from == x.to)
+    {
+        return None;
+    }
+
+    let previous_weights = updates
+        .iter()
+        .map(|update| std::mem::replace(&mut graph_matrix[update.from][update.to], update.distance))
+        .collect();
+    Some(previous_weights)
+}
+
+/// Applies `updates` to `graph_matrix` and repairs `shortest_path_tree` accordingly.
+/// The tree must be the result of `graph_matrix::shortest_path_tree` on the matrix before the updates,
+/// afterwards it equals a full recomputation on the updated matrix.
+/// Returns the nodes whose tree entry changed, in ascending order.
+pub fn update_shortest_path_tree(
+    graph_matrix: &mut GraphMatrix,
+    shortest_path_tree: &mut ShortestPathTree,
+    updates: &[EdgeUpdate],
+) -> Option<Vec<usize>> {
+    if shortest_path_tree.len() != graph_matrix.len() || shortest_path_tree.is_empty() {
+        return None;
+    }
+    let previous_weights = apply_edge_updates(graph_matrix, updates)?;
+    repair_shortest_path_tree(graph_matrix, shortest_path_tree, updates, &previous_weights)
+}
+
+/// Repairs a tree of the matrix before `updates` for the matrix after them, the matrix is not changed.
+/// `previous_weights` are the weights returned by [`apply_edge_updates`], so one updated matrix
+/// serves any number of trees. Returns the nodes whose tree entry changed, in ascending order.
+pub fn repair_shortest_path_tree(
+    graph_matrix: &GraphMatrix,
+    shortest_path_tree: &mut ShortestPathTree,
+    updates: &[EdgeUpdate],
+    previous_weights: &[u32],
+) -> Option<Vec<usize>> {
+    let node_count = graph_matrix.len();
+    if shortest_path_tree.len() != node_count
+        || previous_weights.len() != updates.len()
+        || updates
+            .iter()
+            .any(|x| x.from >= node_count || x.to >= node_count || x.from == x.to)
+    {
+        return None;
+    }
+    let source = shortest_path_tree.first()?.from;
+
+    let old_distances: Vec<u32> = shortest_path_tree.iter().map(|x| x.distance).collect();
+    let mut distances = old_distances.clone();
+
+    let mut increased: Vec<(usize, usize)> = Vec::new();
+    let mut decreased: Vec<(usize, usize)> = Vec::new();
+    for (update, &old) in updates.iter().zip(previous_weights.iter()) {
+        if update.distance == 0 || (old != 0 && update.distance > old) {
+            increased.push((update.from, update.to));
+        } else if old == 0 || update.distance < old {
+            decreased.push((update.from, update.to));
+        }
+    }
+
+    // subtrees below increased tree edges lose their distances
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); node_count];
+    for node in shortest_path_tree.iter().filter(|x| x.previous != x.to) {
+        children[node.previous].push(node.to);
+    }
+    let mut affected = vec![false; node_count];
+    let mut stack: Vec<usize> = increased
+        .iter()
+        .filter(|&&(from, to)| to != source && shortest_path_tree[to].previous == from)
+        .map(|&(_, to)| to)
+        .collect();
+    let mut affected_nodes: Vec<usize> = Vec::new();
+    while let Some(node) = stack.pop() {
+        if !affected[node] {
+            affected[node] = true;
+            affected_nodes.push(node);
+            stack.extend(children[node].iter().copied());
+        }
+    }
+    for &node in affected_nodes.iter() {
+        distances[node] = u32::MAX;
+    }
+
+    // tentative distances from the unaffected neighbours
+    let mut heap: BinaryHeap<Reverse<(u32, usize)>> = BinaryHeap::new();
+    for &node in affected_nodes.iter() {
+        for from in 0..node_count {
+            let weight = graph_matrix[from][node];
+            if weight > 0 && !affected[from] && distances[from] != u32::MAX {
+                distances[node] = distances[node].min(distances[from].saturating_add(weight));
+            }
+        }
+        if distances[node] != u32::MAX {
+            heap.push(Reverse((distances[node], node)));
+        }
+    }
+
+    for &(from, to) in decreased.iter() {
+        let weight = graph_matrix[from][to];
+        if weight > 0 && distances[from] != u32::MAX {
+            let distance = distances[from].saturating_add(weight);
+            if distance < distances[to] {
+                distances[to] = distance;
+                heap.push(Reverse((distance, to)));
+            }
+        }
+    }
+
+    // settling the new distances, every popped distance is final
+    while let Some(Reverse((distance, node))) = heap.pop() {
+        if distance > distances[node] {
+            continue;
+        }
+        for (to, &weight) in graph_matrix[node].iter().enumerate() {
+            if weight > 0 {
+                let new_distance = distance.saturating_add(weight);
+                if new_distance < distances[to] {
+                    distances[to] = new_distance;
+                    heap.push(Reverse((new_distance, to)));
+                }
+            }
+        }
+    }
+
+    // previous nodes may change where a distance changed, next to it, or at an updated edge
+    let mut candidates = vec![false; node_count];
+    for &node in affected_nodes.iter() {
+        candidates[node] = true;
+    }
+    for update in updates {
+        candidates[update.to] = true;
+    }
+    for node in 0..node_count {
+        if distances[node] != old_distances[node] {
+            candidates[node] = true;
+            for (to, &weight) in graph_matrix[node].iter().enumerate() {
+                if weight > 0 {
+                    candidates[to] = true;
+                }
+            }
+        }
+    }
+    candidates[source] = false;
+
+    let mut changed: Vec<usize> = Vec::new();
+    for node in 0..node_count {
+        if !candidates[node] {
+            continue;
+        }
+        let previous = canonical_previous(graph_matrix, &distances, node);
+        let tree_node = &mut shortest_path_tree[node];
+        if tree_node.distance != distances[node] || tree_node.previous != previous {
+            tree_node.distance = distances[node];
+            tree_node.previous = previous;
+            changed.push(node);
+        }
+    }
+
+    Some(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::graph_matrix;
+    use crate::dijkstra::utils::*;
+    use crate::graph_samples;
+    use crate::random_graphs::*;
+
+    #[test]
+    fn update_shortest_path_tree_sample1_test() {
+        let (graph, _) = graph_samples::sample1();
+        let (names, mut matrix) = graph_matrix::graph_map_to_matrix(&graph);
+        let lookup = name_lookup_map(&names);
+        let mut tree = graph_matrix::shortest_path_tree(&matrix, lookup["A"]).unwrap();
+
+        // closing the road C-D in both directions
+        let (c, d) = (lookup["C"], lookup["D"]);
+        let updates = [
+            EdgeUpdate {
+                from: c,
+                to: d,
+                distance: 0,
+            },
+            EdgeUpdate {
+                from: d,
+                to: c,
+                distance: 0,
+            },
+        ];
+        let changed = update_shortest_path_tree(&mut matrix, &mut tree, &updates).unwrap();
+        assert_eq!(
+            tree,
+            graph_matrix::shortest_path_tree(&matrix, lookup["A"]).unwrap()
+        );
+        assert_eq!(changed, [d]);
+        assert_eq!(
+            build_shortest_path_from_tree(d, &tree).unwrap().distance,
+            25
+        );
+
+        // a change far from any tree edge leaves the tree alone
+        let (b, h) = (lookup["B"], lookup["H"]);
+        let updates = [EdgeUpdate {
+            from: b,
+            to: h,
+            distance: 12,
+        }];
+        let changed = update_shortest_path_tree(&mut matrix, &mut tree, &updates).unwrap();
+        assert!(changed.is_empty());
+
+        // and reopening the road restores the original tree
+        let updates = [
+            EdgeUpdate {
+                from: c,
+                to: d,
+                distance: 7,
+            },
+            EdgeUpdate {
+                from: d,
+                to: c,
+                distance: 7,
+            },
+            EdgeUpdate {
+                from: b,
+                to: h,
+                distance: 11,
+            },
+        ];
+        update_shortest_path_tree(&mut matrix, &mut tree, &updates).unwrap();
+        let (_, original) = graph_matrix::graph_map_to_matrix(&graph);
+        assert_eq!(
+            tree,
+            graph_matrix::shortest_path_tree(&original, lookup["A"]).unwrap()
+        );
+
+        let updates = [EdgeUpdate {
+            from: c,
+            to: c,
+            distance: 1,
+        }];
+        assert!(update_shortest_path_tree(&mut matrix, &mut tree, &updates).is_none());
+    }
+
+    #[test]
+    fn update_shortest_path_tree_random_test() {
+        let mut seed = 7;
+        for round in 0..60 {
+            let node_count = 15;
+            let mut matrix = random_graph_matrix(node_count, 15 + round % 20, 9, &mut seed);
+            let source = round as usize % node_count;
+            let mut tree = graph_matrix::shortest_path_tree(&matrix, source).unwrap();
+
+            for _ in 0..5 {
+                let batch_size = next_random(&mut seed) % 6 + 1;
+                let mut updates: Vec<EdgeUpdate> = Vec::new();
+                while (updates.len() as u64) < batch_size {
+                    let from = (next_random(&mut seed) % node_count as u64) as usize;
+                    let to = (next_random(&mut seed) % node_count as u64) as usize;
+                    if from == to {
+                        continue;
+                    }
+                    // removals, weight changes and insertions
+                    let distance = match next_random(&mut seed) % 3 {
+                        0 => 0,
+                        _ => (next_random(&mut seed) % 9 + 1) as u32,
+                    };
+                    updates.push(EdgeUpdate { from, to, distance });
+                }
+
+                let before = tree
+                    .iter()
+                    .map(|x| (x.distance, x.previous))
+                    .collect::<Vec<_>>();
+                let changed = update_shortest_path_tree(&mut matrix, &mut tree, &updates).unwrap();
+                let expected = graph_matrix::shortest_path_tree(&matrix, source).unwrap();
+                assert_eq!(tree, expected);
+
+                let expected_changed: Vec<usize> = (0..node_count)
+                    .filter(|&x| before[x] != (expected[x].distance, expected[x].previous))
+                    .collect();
+                assert_eq!(changed, expected_changed);
+            }
+        }
+    }
+
+    #[test]
+    fn repair_shortest_path_tree_shared_matrix_test() {
+        let mut seed = 13;
+        let node_count = 20;
+        let mut matrix = random_graph_matrix(node_count, 20, 9, &mut seed);
+        let mut trees: Vec<ShortestPathTree> = (0..node_count)
+            .map(|source| graph_matrix::shortest_path_tree(&matrix, source).unwrap())
+            .collect();
+
+        let updates = [
+            EdgeUpdate {
+                from: 1,
+                to: 2,
+                distance: 0,
+            },
+            EdgeUpdate {
+                from: 3,
+                to: 4,
+                distance: 1,
+            },
+            EdgeUpdate {
+                from: 1,
+                to: 2,
+                distance: 4,
+            },
+        ];
+        // the matrix is updated once and every tree is repaired against it
+        let previous_weights = apply_edge_updates(&mut matrix, &updates).unwrap();
+        assert_eq!(previous_weights[2], 0);
+        for (source, tree) in trees.iter_mut().enumerate() {
+            repair_shortest_path_tree(&matrix, tree, &updates, &previous_weights).unwrap();
+            assert_eq!(
+                *tree,
+                graph_matrix::shortest_path_tree(&matrix, source).unwrap()
+            );
+        }
+
+        assert!(repair_shortest_path_tree(&matrix, &mut trees[0], &updates, &[]).is_none());
+        let invalid = [EdgeUpdate {
+            from: 0,
+            to: node_count,
+            distance: 1,
+        }];
+        assert!(apply_edge_updates(&mut matrix, &invalid).is_none());
+    }
+}
@@ -0,0 +1,253 @@
+// Routing engine with a shortest path tree cache
+// The engine owns the graph, so every change goes through it and the cached trees never go stale:
+// - edge changes update the matrix once and repair each cached tree with `dynamic::repair_shortest_path_tree`,
+// - node changes renumber the graph and clear the cache.
+// Trees are cached on node ids and named only when asked for, through `ShortestPathTreeView`.
+// The least recently used tree is evicted once the cache is full.
+// Edges are undirected, as in `graph_map_to_matrix`, so maps with one-way or asymmetric edges are refused.
+
+use crate::dijkstra::dynamic::{apply_edge_updates, repair_shortest_path_tree};
+use crate::dijkstra::graph_matrix;
+use crate::graph_builder::*;
+use crate::graph_type::*;
+use std::collections::HashMap;
+
+struct CachedTree {
+    tree: ShortestPathTree,
+    last_used: u64,
+}
+
+pub struct RoutingEngine {
+    graph_map: GraphMap,
+    names: NodeNames,
+    matrix: GraphMatrix,
+    capacity: usize,
+    cache: HashMap<usize, CachedTree>,
+    clock: u64,
+}
+
+impl RoutingEngine {
+    /// Takes over a validated undirected graph, at most `capacity` trees are kept (at least one)
+    pub fn new(graph_map: GraphMap, capacity: usize) -> Result<Self, GraphError> {
+        validate_undirected_graph_map(&graph_map)?;
+        let GraphMatrixNamed { names, matrix } =
+            graph_matrix::graph_map_to_matrix_named(&graph_map);
+        Ok(RoutingEngine {
+            graph_map,
+            names,
+            matrix,
+            capacity: capacity.max(1),
+            cache: HashMap::new(),
+            clock: 0,
+        })
+    }
+
+    pub fn graph(&self) -> &GraphMap {
+        &self.graph_map
+    }
+
+    pub fn is_cached(&self, source: &str) -> bool {
+        self.names
+            .id(source)
+            .is_some_and(|id| self.cache.contains_key(&id.index()))
+    }
+
+    pub fn cached_count(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Cached tree of `source`, computed on the first request and named on demand
+    pub fn shortest_path_tree(&mut self, source: &str) -> Option<ShortestPathTreeView<'_>> {
+        let source = self.names.id(source)?.index();
+        self.clock += 1;
+
+        if !self.cache.contains_key(&source) {
+            if self.cache.len() >= self.capacity {
+                let oldest = *self
+                    .cache
+                    .iter()
+                    .min_by_key(|(_, cached)| cached.last_used)
+                    .unwrap()
+                    .0;
+                self.cache.remove(&oldest);
+            }
+
+            let tree = graph_matrix::shortest_path_tree(&self.matrix, source)?;
+            self.cache.insert(source, CachedTree { tree, last_used: 0 });
+        }
+
+        let cached = self.cache.get_mut(&source).unwrap();
+        cached.last_used = self.clock;
+        Some(self.names.view_tree(&cached.tree))
+    }
+
+    pub fn shortest_path(&mut self, from: &str, to: &str) -> Option<ShortestPathNamed> {
+        self.shortest_path_tree(from)?.shortest_path(to)
+    }
+
+    pub fn add_node(&mut self, node: &str) -> Result<(), GraphError> {
+        self.graph_map.add_node(node)?;
+        self.renumber();
+        Ok(())
+    }
+
+    /// Removes the node and its edges
+    pub fn remove_node(&mut self, node: &str) -> Result<(), GraphError> {
+        self.graph_map.remove_node(node)?;
+        self.renumber();
+        Ok(())
+    }
+
+    pub fn add_edge(&mut self, a: &str, b: &str, distance: u32) -> Result<(), GraphError> {
+        self.graph_map.add_undirected_edge(a, b, distance)?;
+        self.repair(a, b, distance);
+        Ok(())
+    }
+
+    pub fn update_edge(&mut self, a: &str, b: &str, distance: u32) -> Result<(), GraphError> {
+        self.graph_map.update_undirected_edge(a, b, distance)?;
+        self.repair(a, b, distance);
+        Ok(())
+    }
+
+    pub fn remove_edge(&mut self, a: &str, b: &str) -> Result<(), GraphError> {
+        self.graph_map.remove_undirected_edge(a, b)?;
+        self.repair(a, b, 0);
+        Ok(())
+    }
+
+    /// Node indexes follow the sorted names, so any node change invalidates all trees
+    fn renumber(&mut self) {
+        let GraphMatrixNamed { names, matrix } =
+            graph_matrix::graph_map_to_matrix_named(&self.graph_map);
+        self.names = names;
+        self.matrix = matrix;
+        self.cache.clear();
+    }
+
+    /// Applies the edge change to the matrix and to every cached tree
+    fn repair(&mut self, a: &str, b: &str, distance: u32) {
+        let a = self.names.id(a).unwrap().index();
+        let b = self.names.id(b).unwrap().index();
+        let updates = [
+            EdgeUpdate {
+                from: a,
+                to: b,
+                distance,
+            },
+            EdgeUpdate {
+                from: b,
+                to: a,
+                distance,
+            },
+        ];
+
+        // the matrix is updated once, every cached tree is repaired against it
+        let previous_weights = apply_edge_updates(&mut self.matrix, &updates).unwrap();
+        for cached in self.cache.values_mut() {
+            repair_shortest_path_tree(&self.matrix, &mut cached.tree, &updates, &previous_weights)
+                .unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::graph_vector;
+    use crate::graph_samples;
+
+    fn fresh_tree(graph_map: &GraphMap, source: &str) -> ShortestPathTreeNamed {
+        let graph_vector = graph_vector::graph_map_to_vector(graph_map).unwrap();
+        graph_vector::shortest_path_tree(&graph_vector, source).unwrap()
+    }
+
+    #[test]
+    fn routing_engine_cache_test() {
+        let (graph, expected_shortest_paths) = graph_samples::sample1();
+        let mut engine = RoutingEngine::new(graph, 2).unwrap();
+
+        for expected in expected_shortest_paths.iter().filter(|x| x.from == "A") {
+            let shortest_path = engine.shortest_path("A", &expected.to).unwrap();
+            assert_eq!(shortest_path.distance, expected.distance);
+        }
+        assert_eq!(engine.cached_count(), 1);
+
+        // the least recently used tree goes first
+        engine.shortest_path_tree("B").unwrap();
+        engine.shortest_path_tree("A").unwrap();
+        engine.shortest_path_tree("C").unwrap();
+        assert!(engine.is_cached("A"));
+        assert!(!engine.is_cached("B"));
+        assert!(engine.is_cached("C"));
+        assert_eq!(engine.cached_count(), 2);
+
+        assert!(engine.shortest_path_tree("X").is_none());
+        assert!(engine.shortest_path("A", "X").is_none());
+    }
+
+    #[test]
+    fn routing_engine_edit_test() {
+        let (graph, _) = graph_samples::sample1();
+        let mut engine = RoutingEngine::new(graph, 3).unwrap();
+        for source in ["A", "E", "I"] {
+            engine.shortest_path_tree(source).unwrap();
+        }
+
+        engine.remove_edge("C", "D").unwrap();
+        engine.update_edge("G", "F", 1).unwrap();
+        engine.add_edge("A", "E", 30).unwrap();
+        assert_eq!(engine.cached_count(), 3);
+        assert!(engine.update_edge("A", "D", 3).is_err());
+
+        for source in ["A", "E", "I"] {
+            let expected = fresh_tree(engine.graph(), source);
+            assert_eq!(
+                engine.shortest_path_tree(source).unwrap().to_named(),
+                expected
+            );
+        }
+        assert_eq!(engine.shortest_path("A", "D").unwrap().distance, 24);
+
+        // node changes clear the cache
+        engine.add_node("J").unwrap();
+        assert_eq!(engine.cached_count(), 0);
+        engine.add_edge("J", "A", 2).unwrap();
+        assert_eq!(engine.shortest_path("J", "D").unwrap().distance, 26);
+
+        engine.remove_node("A").unwrap();
+        assert!(engine.shortest_path("J", "D").is_none());
+        let expected = fresh_tree(engine.graph(), "E");
+        assert_eq!(engine.shortest_path_tree("E").unwrap().to_named(), expected);
+    }
+
+    #[test]
+    fn routing_engine_validation_test() {
+        let mut graph = GraphMap::new();
+        graph
+            .entry("A".to_string())
+            .or_default()
+            .insert("B".to_string(), 1);
+        assert_eq!(
+            RoutingEngine::new(graph, 1).err(),
+            Some(GraphError::MissingNode("B".to_string()))
+        );
+
+        // one-way and asymmetric edges are refused
+        let graph = graph_samples::build_directed_graph(&[("A", "B", 3)]);
+        assert_eq!(
+            RoutingEngine::new(graph, 1).err(),
+            Some(GraphError::MissingEdge("B".to_string(), "A".to_string()))
+        );
+        let graph = graph_samples::build_directed_graph(&[("A", "B", 3), ("B", "A", 50)]);
+        assert_eq!(
+            RoutingEngine::new(graph, 1).err(),
+            Some(GraphError::ConflictingDistance {
+                from: "A".to_string(),
+                to: "B".to_string(),
+                existing: 3,
+                new: 50
+            })
+        );
+    }
+}
@@ -0,0 +1,374 @@
+// Dijkstra on compressed sparse row graphs
+// The edges of a node are contiguous, so a search touches only the edges it needs and
+// the memory stays at one offset per node and one target and weight per edge.
+// Node ids follow the sorted node names as in `graph_map_to_matrix`, but edges keep their direction.
+// Nodes are settled by (distance, id), which gives the same tree as `graph_matrix::shortest_path_tree`
+// on symmetric graphs.
+
+use crate::dijkstra::utils::*;
+use crate::graph_type::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Builds the arrays from `(from, to, distance)` edges, which are sorted in place
+fn csr_from_edges(node_count: usize, edges: &mut [(u32, u32, u32)]) -> GraphCsr {
+    edges.sort_unstable();
+
+    let mut offsets: Vec<u32> = vec![0; node_count + 1];
+    for &(from, _, _) in edges.iter() {
+        offsets[from as usize + 1] += 1;
+    }
+    for node in 0..node_count {
+        offsets[node + 1] += offsets[node];
+    }
+
+    GraphCsr {
+        offsets,
+        targets: edges.iter().map(|x| x.1).collect(),
+        weights: edges.iter().map(|x| x.2).collect(),
+    }
+}
+
+/// Converts map based graph into CSR graph, edges to unknown nodes are left out
+pub fn graph_map_to_csr(graph_map: &GraphMap) -> (Vec<String>, GraphCsr) {
+    let graph = graph_map_to_csr_named(graph_map);
+    (graph.names.into_names(), graph.csr)
+}
+
+/// Converts vector based graph into CSR graph, edges to unknown nodes are left out
+pub fn graph_vector_to_csr(graph_vector: &GraphVector) -> (Vec<String>, GraphCsr) {
+    let graph = graph_vector_to_csr_named(graph_vector);
+    (graph.names.into_names(), graph.csr)
+}
+
+pub fn graph_matrix_to_csr(graph_matrix: &GraphMatrix) -> GraphCsr {
+    let mut edges: Vec<(u32, u32, u32)> = Vec::new();
+    for (from, row) in graph_matrix.iter().enumerate() {
+        for (to, &distance) in row.iter().enumerate() {
+            if distance > 0 {
+                edges.push((from as u32, to as u32, distance));
+            }
+        }
+    }
+    csr_from_edges(graph_matrix.len(), &mut edges)
+}
+
+/// Search over the raw CSR arrays, shared by owned and borrowed graphs.
+/// An edge is only followed if `edge_allowed(from, to, distance)` holds.
+pub(crate) fn shortest_path_tree_arrays(
+    offsets: &[u32],
+    targets: &[u32],
+    weights: &[u32],
+    source: usize,
+    edge_allowed: impl Fn(usize, usize, u32) -> bool,
+) -> Option<ShortestPathTree> {
+    let node_count = offsets.len().saturating_sub(1);
+    if source >= node_count {
+        return None;
+    }
+
+    let mut shortest_path_tree: ShortestPathTree = (0..node_count)
+        .map(|node| ShortestPathTreeNode {
+            from: source,
+            to: node,
+            distance: u32::MAX,
+            previous: node,
+        })
+        .collect();
+    let mut settled = vec![false; node_count];
+    let mut heap: BinaryHeap<Reverse<(u32, usize)>> = BinaryHeap::new();
+
+    shortest_path_tree[source].distance = 0;
+    heap.push(Reverse((0, source)));
+
+    while let Some(Reverse((distance, node))) = heap.pop() {
+        if settled[node] {
+            continue;
+        }
+        settled[node] = true;
+
+        let range = offsets[node] as usize..offsets[node + 1] as usize;
+        for (&to, &weight) in targets[range.clone()].iter().zip(weights[range].iter()) {
+            let to = to as usize;
+            if settled[to] || !edge_allowed(node, to, weight) {
+                continue;
+            }
+            let record = &mut shortest_path_tree[to];
+            let new_distance = distance.saturating_add(weight);
+            if record.distance > new_distance {
+                record.distance = new_distance;
+                record.previous = node;
+                heap.push(Reverse((new_distance, to)));
+            }
+        }
+    }
+
+    Some(shortest_path_tree)
+}
+
+/// Nodes unreachable from `source` get `u32::MAX` as distance and themselves as previous node
+pub fn shortest_path_tree(graph_csr: &impl CsrGraph, source: usize) -> Option<ShortestPathTree> {
+    shortest_path_tree_arrays(
+        graph_csr.offsets(),
+        graph_csr.targets(),
+        graph_csr.weights(),
+        source,
+        |_, _, _| true,
+    )
+}
+
+/// Runs one search seeded with all `sources` at distance zero, edges keep their direction.
+/// The `from` field of each tree node holds its nearest source, as in
+/// `graph_matrix::multi_source_shortest_path_tree`, nodes not reachable from any source point to themselves.
+pub fn multi_source_shortest_path_tree(
+    graph_csr: &impl CsrGraph,
+    sources: &[usize],
+) -> Option<ShortestPathTree> {
+    let node_count = graph_csr.node_count();
+    if sources.is_empty() || sources.iter().any(|&source| source >= node_count) {
+        return None;
+    }
+
+    let mut shortest_path_tree: ShortestPathTree = (0..node_count)
+        .map(|node| ShortestPathTreeNode {
+            from: node,
+            to: node,
+            distance: u32::MAX,
+            previous: node,
+        })
+        .collect();
+    let mut settled = vec![false; node_count];
+    let mut heap: BinaryHeap<Reverse<(u32, usize)>> = BinaryHeap::new();
+
+    // every source starts at zero distance and owns itself
+    for &source in sources {
+        shortest_path_tree[source].distance = 0;
+        heap.push(Reverse((0, source)));
+    }
+
+    while let Some(Reverse((distance, node))) = heap.pop() {
+        if settled[node] {
+            continue;
+        }
+        settled[node] = true;
+
+        // the nearest source is inherited along with the previous node
+        let node_source = shortest_path_tree[node].from;
+        for (to, weight) in graph_csr.edges(node) {
+            if settled[to] {
+                continue;
+            }
+            let record = &mut shortest_path_tree[to];
+            let new_distance = distance.saturating_add(weight);
+            if record.distance > new_distance {
+                record.distance = new_distance;
+                record.previous = node;
+                record.from = node_source;
+                heap.push(Reverse((new_distance, to)));
+            }
+        }
+    }
+
+    Some(shortest_path_tree)
+}
+
+/// Same as [`graph_map_to_csr`], the graph keeps the node names
+pub fn graph_map_to_csr_named(graph_map: &GraphMap) -> GraphCsrNamed {
+    let names = NodeNames::sorted(graph_map.keys());
+
+    let mut edges: Vec<(u32, u32, u32)> = Vec::new();
+    for (from, map) in graph_map {
+        let from = names.id(from).unwrap().0;
+        for (to, distance) in map {
+            if let Some(to) = names.id(to) {
+                edges.push((from, to.0, *distance));
+            }
+        }
+    }
+
+    let csr = csr_from_edges(names.len(), &mut edges);
+    GraphCsrNamed { names, csr }
+}
+
+/// Same as [`graph_vector_to_csr`], the graph keeps the node names
+pub fn graph_vector_to_csr_named(graph_vector: &GraphVector) -> GraphCsrNamed {
+    let names = NodeNames::sorted(graph_vector.iter().map(|x| &x.0));
+
+    let mut edges: Vec<(u32, u32, u32)> = Vec::new();
+    for (from, list) in graph_vector {
+        let from = names.id(from).unwrap().0;
+        for (to, distance) in list {
+            if let Some(to) = names.id(to) {
+                edges.push((from, to.0, *distance));
+            }
+        }
+    }
+
+    let csr = csr_from_edges(names.len(), &mut edges);
+    GraphCsrNamed { names, csr }
+}
+
+/// Named tree of a map based graph, computed on its CSR form.
+/// Converts the graph on every call, see [`shortest_path_tree_by_name`] for repeated queries.
+pub fn shortest_path_tree_named(
+    graph_map: &GraphMap,
+    source: &str,
+) -> Option<ShortestPathTreeNamed> {
+    let graph = graph_map_to_csr_named(graph_map);
+    let tree = shortest_path_tree_by_name(&graph, source)?;
+    Some(graph.names.resolve_tree(&tree))
+}
+
+/// Numbered tree of a named graph built once with [`graph_map_to_csr_named`] or
+/// [`graph_vector_to_csr_named`], names are read through [`NodeNames::view_tree`]
+pub fn shortest_path_tree_by_name(graph: &GraphCsrNamed, source: &str) -> Option<ShortestPathTree> {
+    shortest_path_tree(&graph.csr, graph.names.id(source)?.index())
+}
+
+/// Shortest path between named nodes, the search itself only sees node ids
+pub fn shortest_path_named(
+    graph: &GraphCsrNamed,
+    from: &str,
+    to: &str,
+) -> Option<ShortestPathNamed> {
+    let to = graph.names.id(to)?;
+    let tree = shortest_path_tree_by_name(graph, from)?;
+    let shortest_path = build_shortest_path_from_tree(to.index(), &tree)?;
+    Some(graph.names.resolve_path(&shortest_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::{graph_matrix, graph_vector};
+    use crate::graph_samples;
+
+    #[test]
+    fn graph_csr_conversion_test() {
+        let (graph, _) = graph_samples::sample2();
+        let (names, csr) = graph_map_to_csr(&graph);
+        assert_eq!(names, ["A", "B", "C", "D", "E"]);
+        assert_eq!(csr.node_count(), 5);
+        assert_eq!(csr.edge_count(), 14);
+        // B: A 6, C 5, D 2, E 2
+        assert_eq!(
+            csr.edges(1).collect::<Vec<_>>(),
+            [(0, 6), (2, 5), (3, 2), (4, 2)]
+        );
+
+        let graph_vector = graph_vector::graph_map_to_vector(&graph).unwrap();
+        assert_eq!(graph_vector_to_csr(&graph_vector), (names, csr.clone()));
+
+        let (_, matrix) = graph_matrix::graph_map_to_matrix(&graph);
+        assert_eq!(graph_matrix_to_csr(&matrix), csr);
+
+        let (names, csr) = graph_map_to_csr(&GraphMap::new());
+        assert!(names.is_empty());
+        assert_eq!(csr.node_count(), 0);
+    }
+
+    #[test]
+    fn graph_csr_shortest_path_tree_test() {
+        for (graph, expected_shortest_paths) in [
+            graph_samples::sample1(),
+            graph_samples::sample2(),
+            graph_samples::sample3(),
+            graph_samples::sample4(),
+        ] {
+            let (_, matrix) = graph_matrix::graph_map_to_matrix(&graph);
+            let csr = graph_matrix_to_csr(&matrix);
+            for source in 0..matrix.len() {
+                assert_eq!(
+                    shortest_path_tree(&csr, source),
+                    graph_matrix::shortest_path_tree(&matrix, source)
+                );
+            }
+
+            let tree = shortest_path_tree_named(&graph, &expected_shortest_paths[0].from).unwrap();
+            for expected in expected_shortest_paths {
+                assert_eq!(
+                    build_shortest_path_from_tree_named(&expected.to, &tree).unwrap(),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn graph_csr_named_test() {
+        let (graph, expected_shortest_paths) = graph_samples::sample1();
+        let graph_csr_named = graph_map_to_csr_named(&graph);
+        assert_eq!(graph_csr_named.names.len(), graph.len());
+        assert_eq!(graph_csr_named.names.id("A"), Some(NodeId(0)));
+        assert_eq!(graph_csr_named.names.name(NodeId(8)), "I");
+
+        for expected in expected_shortest_paths {
+            let shortest_path =
+                shortest_path_named(&graph_csr_named, &expected.from, &expected.to).unwrap();
+            assert_eq!(shortest_path, expected);
+        }
+        assert!(shortest_path_named(&graph_csr_named, "A", "X").is_none());
+
+        let graph_vector = graph_vector::graph_map_to_vector(&graph).unwrap();
+        assert_eq!(graph_vector_to_csr_named(&graph_vector), graph_csr_named);
+
+        // interned names keep their first id
+        let mut names = NodeNames::new();
+        assert_eq!(names.intern("B"), NodeId(0));
+        assert_eq!(names.intern("A"), NodeId(1));
+        assert_eq!(names.intern("B"), NodeId(0));
+        assert_eq!(names.names(), ["B", "A"]);
+    }
+
+    #[test]
+    fn shortest_path_tree_view_test() {
+        let (graph, expected_shortest_paths) = graph_samples::sample1();
+        let graph_csr_named = graph_map_to_csr_named(&graph);
+        let names = &graph_csr_named.names;
+        let tree = shortest_path_tree_by_name(&graph_csr_named, "A").unwrap();
+        let view = names.view_tree(&tree);
+        assert!(shortest_path_tree_by_name(&graph_csr_named, "X").is_none());
+
+        for expected in expected_shortest_paths.iter().filter(|x| x.from == "A") {
+            assert_eq!(view.distance(&expected.to), Some(expected.distance));
+            assert_eq!(view.shortest_path(&expected.to).as_ref(), Some(expected));
+            let previous = &expected.path[expected.path.len().max(2) - 2];
+            assert_eq!(view.previous(&expected.to), Some(previous.as_str()));
+        }
+        assert_eq!(view.to_named(), names.resolve_tree(&tree));
+        assert!(view.distance("X").is_none());
+        assert!(view.shortest_path("X").is_none());
+
+        // unreachable nodes have no distance, previous node or path
+        let (_, csr) = graph_vector_to_csr(&vec![
+            ("A".to_string(), vec![]),
+            ("B".to_string(), vec![("A".to_string(), 1)]),
+        ]);
+        let names = NodeNames::sorted(["B".to_string(), "A".to_string()].iter());
+        let tree = shortest_path_tree(&csr, 0).unwrap();
+        let view = names.view_tree(&tree);
+        assert_eq!(view.shortest_path("A").unwrap().path, ["A", "A"]);
+        assert!(view.distance("B").is_none());
+        assert!(view.previous("B").is_none());
+        assert!(view.shortest_path("B").is_none());
+    }
+
+    #[test]
+    fn graph_csr_directed_test() {
+        // one-way edges A->B->C, and C->A
+        let graph_vector: GraphVector = vec![
+            ("A".to_string(), vec![("B".to_string(), 1)]),
+            ("B".to_string(), vec![("C".to_string(), 2)]),
+            ("C".to_string(), vec![("A".to_string(), 5)]),
+            ("D".to_string(), vec![("X".to_string(), 1)]),
+        ];
+        let (_, csr) = graph_vector_to_csr(&graph_vector);
+        assert_eq!(csr.edge_count(), 3);
+
+        let tree = shortest_path_tree(&csr, 1).unwrap();
+        let distances: Vec<u32> = tree.iter().map(|x| x.distance).collect();
+        assert_eq!(distances, [7, 0, 2, u32::MAX]);
+        assert_eq!(tree[3].previous, 3);
+        assert!(shortest_path_tree(&csr, 4).is_none());
+    }
+}
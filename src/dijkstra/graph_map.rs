@@ -7,111 +7,137 @@
 // https://www.youtube.com/watch?v=pVfj6mxhdMw
 // https://www.geeksforgeeks.org/dijkstras-shortest-path-algorithm-greedy-algo-7/
 
+use std::collections::HashMap;
 use crate::graph_type::*;
 use crate::dijkstra::utils::*;
+use crate::analytics::components::check_reachability;
+use crate::dijkstra::query::QueryOptions;
+use crate::dijkstra::graph_csr;
 
+/// Nodes unreachable from `source` get `u32::MAX` as distance and themselves as previous node.
+/// Runs on the CSR form of the graph, see [`graph_csr::shortest_path_tree_named`].
+/// The CSR form is rebuilt on every call, graphs queried many times should be converted once
+/// with [`graph_csr::graph_map_to_csr_named`] and queried with
+/// [`graph_csr::shortest_path_tree_by_name`].
 pub fn shortest_path_tree(graph_map: &GraphMap, source: &str) -> Option<ShortestPathTreeNamed> {
-    struct PathRecord {
-        name: String,
-        distance: Option<u32>,
-        prev_node: String,
-        visited: bool,
-    }
+    graph_csr::shortest_path_tree_named(graph_map, source)
+}
 
-    // check if from and to nodes exists in graph
-    if graph_map.get(source).is_none() {
+/// Same as [`shortest_path_tree`], but skips the nodes and edges excluded by `options`.
+/// Returns `None` if the source itself is excluded.
+pub fn shortest_path_tree_with_options(
+    graph_map: &GraphMap,
+    source: &str,
+    options: &QueryOptions,
+) -> Option<ShortestPathTreeNamed> {
+    if !options.allows_node(&source.to_string()) {
         return None;
     }
 
-    let mut path_table: Vec<PathRecord> = Vec::new();
-    for node in graph_map.keys() {
-        let path_record = PathRecord {
-            name: node.to_string(),
-            distance: None,
-            prev_node: node.to_string(),
-            visited: false,
-        };
-        path_table.push(path_record);
-    }
-
-    // make starting node distance as zero
-    path_table
-        .iter_mut()
-        .find(|r| r.name == source)
-        .unwrap()
-        .distance = Some(0);
-
-    loop {
-        // check for min distance and not visited
-        let mut records: Vec<_> = path_table
-            .iter_mut()
-            .filter(|r| !r.visited && r.distance.is_some())
-            .collect();
-        if records.is_empty() {
-            break;
-        }
+    // searching on node ids, names are only looked up by the filter and for the result
+    let graph = graph_csr::graph_map_to_csr_named(graph_map);
+    let source = graph.names.id(source)?;
+    let names = graph.names.names();
+    let excluded = options.excluded_ids(names.len(), |x| graph.names.id(x).map(NodeId::index));
+    let shortest_path_tree = graph_csr::shortest_path_tree_arrays(
+        &graph.csr.offsets,
+        &graph.csr.targets,
+        &graph.csr.weights,
+        source.index(),
+        |from, to, distance| {
+            excluded.allows_edge(from, to)
+                && options.filter_allows(&names[from], &names[to], distance)
+        },
+    )?;
 
-        records.sort_by(|a, b| b.distance.cmp(&a.distance));
+    Some(graph.names.resolve_tree(&shortest_path_tree))
+}
 
-        let mut cur_record = records.pop().unwrap();
+pub fn shortest_path(graph_map: &GraphMap, from: &str, to: &str) -> Option<ShortestPathNamed> {
+    graph_map.get(to)?;
+    let shortest_path_tree = shortest_path_tree(graph_map, from)?;
 
-        let node_name = cur_record.name.clone();
-        let node_distance = cur_record.distance.unwrap();
-        cur_record.visited = true;
+    build_shortest_path_from_tree_named(to, &shortest_path_tree)
+}
 
-        let node = graph_map.get(&node_name);
-        if node.is_none() {
-            panic!("The node {} does not exists in the given graph!", node_name);
-        }
+/// Shortest path avoiding the nodes and edges excluded by `options`
+pub fn shortest_path_with_options(
+    graph_map: &GraphMap,
+    from: &str,
+    to: &str,
+    options: &QueryOptions,
+) -> Option<ShortestPathNamed> {
+    if graph_map.get(from).is_none() || graph_map.get(to).is_none() {
+        return None;
+    }
 
-        let edges = node.unwrap();
-        for (edge_name, edge_distance) in edges {
-            let path_record = path_table
-                .iter_mut()
-                .find(|r| !r.visited && r.name == *edge_name);
-            if path_record.is_some() {
-                let record = path_record.unwrap();
-                if record.distance.is_none()
-                    || record.distance.unwrap() > (edge_distance + node_distance)
-                {
-                    record.distance = Some(edge_distance + node_distance);
-                    record.prev_node = node_name.clone();
-                }
-            }
+    let shortest_path_tree = shortest_path_tree_with_options(graph_map, from, options)?;
+
+    build_shortest_path_from_tree_named(to, &shortest_path_tree)
+}
+
+/// Shortest path reporting why no path was found.
+/// `components` come from `components::connected_components` and are checked before any search runs.
+pub fn shortest_path_checked(
+    graph_map: &GraphMap,
+    components: &HashMap<String, usize>,
+    from: &str,
+    to: &str,
+) -> Result<ShortestPathNamed, ShortestPathError> {
+    // components also hold edge targets that are not nodes of the graph
+    for node in [from, to] {
+        if !graph_map.contains_key(node) {
+            return Err(ShortestPathError::MissingNode(node.to_string()));
         }
     }
+    check_reachability(components, from, to)?;
+
+    let shortest_path_tree =
+        shortest_path_tree(graph_map, from).ok_or(ShortestPathError::Unreachable)?;
+    build_shortest_path_from_tree_named(to, &shortest_path_tree).ok_or(ShortestPathError::Unreachable)
+}
+
+/// Finds the nearest of the given `sources` for every node with a single search on the CSR form.
+/// See [`graph_csr::multi_source_shortest_path_tree`] for how unreachable nodes are reported.
+pub fn multi_source_shortest_path_tree(
+    graph_map: &GraphMap,
+    sources: &[&str],
+) -> Option<ShortestPathTreeNamed> {
+    let graph = graph_csr::graph_map_to_csr_named(graph_map);
 
-    let mut edges: Vec<ShortestPathTreeNodeNamed> = Vec::new();
-    for record in path_table {
-        let edge = ShortestPathTreeNodeNamed {
-            from: source.to_string(),
-            to: record.name.clone(),
-            previous: record.prev_node.clone(),
-            distance: record.distance.unwrap(),
-        };
-        edges.push(edge);
+    let mut source_ids: Vec<usize> = Vec::with_capacity(sources.len());
+    for source in sources {
+        source_ids.push(graph.names.id(source)?.index());
     }
-    edges.sort_by(|a, b| a.to.cmp(&b.to));
 
-    Some(edges)
+    let tree = graph_csr::multi_source_shortest_path_tree(&graph.csr, &source_ids)?;
+    Some(graph.names.resolve_tree(&tree))
 }
 
-pub fn shortest_path(graph_map: &GraphMap, from: &str, to: &str) -> Option<ShortestPathNamed> {
-    if graph_map.get(from).is_none() || graph_map.get(to).is_none() {
-        return None;
+/// Groups the nodes of a multi-source tree by their nearest source (graph Voronoi cells).
+/// Unreachable nodes are left out.
+pub fn voronoi_cells(shortest_path_tree: &ShortestPathTreeNamed) -> HashMap<String, Vec<String>> {
+    let mut cells: HashMap<String, Vec<String>> = HashMap::new();
+    for node in shortest_path_tree {
+        if node.distance == u32::MAX {
+            continue;
+        }
+        cells
+            .entry(node.from.clone())
+            .or_default()
+            .push(node.to.clone());
     }
 
-    let shortest_path_tree = shortest_path_tree(&graph_map, &from);
-    if shortest_path_tree.is_none() {
-        return None;
+    for nodes in cells.values_mut() {
+        nodes.sort();
     }
-
-    build_shortest_path_from_tree_named(&to, &shortest_path_tree.unwrap())
+    cells
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::analytics::components::connected_components;
     use crate::graph_samples;
 
     #[test]
@@ -162,7 +188,7 @@ mod tests {
         let from = "A";
 
         let mut to = "D";
-        let shortest_path_result = shortest_path(&graph, &from, &to);
+        let shortest_path_result = shortest_path(&graph, from, to);
         assert_eq!(
             shortest_path_result.unwrap(),
             ShortestPathNamed {
@@ -174,7 +200,7 @@ mod tests {
         );
 
         to = "E";
-        let shortest_path_result = shortest_path(&graph, &from, &to);
+        let shortest_path_result = shortest_path(&graph, from, to);
         assert_eq!(
             shortest_path_result.unwrap(),
             ShortestPathNamed {
@@ -189,7 +215,7 @@ mod tests {
         );
 
         to = "I";
-        let shortest_path_result = shortest_path(&graph, &from, &to);
+        let shortest_path_result = shortest_path(&graph, from, to);
         assert_eq!(
             shortest_path_result.unwrap(),
             ShortestPathNamed {
@@ -200,4 +226,111 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn multi_source_test() {
+        let (graph, _) = graph_samples::sample1();
+
+        let tree = multi_source_shortest_path_tree(&graph, &["A", "E"]).unwrap();
+        let shortest_path = build_shortest_path_from_tree_named("F", &tree).unwrap();
+        assert_eq!(shortest_path.from, "E");
+        assert_eq!(shortest_path.distance, 10);
+        assert_eq!(shortest_path.path, ["E", "F"]);
+
+        let cells = voronoi_cells(&tree);
+        assert_eq!(cells.len(), 2);
+        assert_eq!(cells["A"], ["A", "B", "C", "G", "H", "I"]);
+        assert_eq!(cells["E"], ["D", "E", "F"]);
+
+        assert!(multi_source_shortest_path_tree(&graph, &["A", "X"]).is_none());
+    }
+
+    #[test]
+    fn multi_source_directed_test() {
+        // one-way edges A->B->C->D and D->A, B is reached from D only through A
+        let mut graph = GraphMap::new();
+        graph.insert("A".to_string(), HashMap::from([("B".to_string(), 1)]));
+        graph.insert("B".to_string(), HashMap::from([("C".to_string(), 1)]));
+        graph.insert("C".to_string(), HashMap::from([("D".to_string(), 1)]));
+        graph.insert("D".to_string(), HashMap::from([("A".to_string(), 1)]));
+
+        // a single source gives the same tree as the plain search
+        for source in ["A", "B", "C", "D"] {
+            assert_eq!(
+                multi_source_shortest_path_tree(&graph, &[source]),
+                shortest_path_tree(&graph, source)
+            );
+        }
+
+        let tree = multi_source_shortest_path_tree(&graph, &["A", "C"]).unwrap();
+        let nearest: Vec<(&str, u32)> = tree.iter().map(|x| (x.from.as_str(), x.distance)).collect();
+        assert_eq!(nearest, [("A", 0), ("A", 1), ("C", 0), ("C", 1)]);
+    }
+
+    #[test]
+    fn shortest_path_with_options_test() {
+        let (graph, _) = graph_samples::sample1();
+
+        // A->B->C->D stays the best route without H and C-F
+        let options = QueryOptions::new()
+            .exclude_node("H".to_string())
+            .exclude_edge("C".to_string(), "F".to_string());
+        let shortest_path = shortest_path_with_options(&graph, "A", "D", &options).unwrap();
+        assert_eq!(shortest_path.distance, 19);
+        assert_eq!(shortest_path.path, ["A", "B", "C", "D"]);
+
+        // A->H->G->F->E is blocked, the detour continues past D
+        let shortest_path = shortest_path_with_options(&graph, "A", "E", &options).unwrap();
+        assert_eq!(shortest_path.distance, 28);
+        assert_eq!(shortest_path.path, ["A", "B", "C", "D", "E"]);
+
+        let tree = shortest_path_tree_with_options(&graph, "A", &options).unwrap();
+        let h = tree.iter().find(|x| x.to == "H").unwrap();
+        assert_eq!(h.distance, u32::MAX);
+        assert!(shortest_path_with_options(&graph, "A", "H", &options).is_none());
+        assert!(shortest_path_with_options(&graph, "H", "A", &options).is_none());
+
+        // edges of distance 8 or more are filtered out, so C is not reachable from A
+        let options = QueryOptions::new().edge_filter(|_, _, distance| distance < 8);
+        let shortest_path = shortest_path_with_options(&graph, "A", "C", &options);
+        assert!(shortest_path.is_none());
+    }
+
+    #[test]
+    fn shortest_path_checked_test() {
+        let (mut graph, _) = graph_samples::sample2();
+        graph.insert("X".to_string(), HashMap::from([("Y".to_string(), 1)]));
+        graph.insert("Y".to_string(), HashMap::from([("X".to_string(), 1)]));
+        // one-way edge, C can not be left towards Z
+        graph.insert("Z".to_string(), HashMap::from([("C".to_string(), 1)]));
+        let components = connected_components(&graph);
+
+        let shortest_path = shortest_path_checked(&graph, &components, "A", "C").unwrap();
+        assert_eq!(shortest_path.distance, 7);
+
+        assert_eq!(
+            shortest_path_checked(&graph, &components, "A", "X"),
+            Err(ShortestPathError::DifferentComponents)
+        );
+        assert_eq!(
+            shortest_path_checked(&graph, &components, "A", "Z"),
+            Err(ShortestPathError::Unreachable)
+        );
+        assert_eq!(
+            shortest_path_checked(&graph, &components, "Q", "A"),
+            Err(ShortestPathError::MissingNode("Q".to_string()))
+        );
+
+        // W is only the target of a dangling edge
+        graph.get_mut("A").unwrap().insert("W".to_string(), 1);
+        let components = connected_components(&graph);
+        assert_eq!(
+            shortest_path_checked(&graph, &components, "A", "W"),
+            Err(ShortestPathError::MissingNode("W".to_string()))
+        );
+        assert_eq!(
+            shortest_path_checked(&graph, &components, "W", "A"),
+            Err(ShortestPathError::MissingNode("W".to_string()))
+        );
+    }
 }
@@ -1,8 +1,33 @@
+use crate::dijkstra::query::QueryOptions;
+use crate::dijkstra::workspace::SearchContext;
 use crate::graph_type::*;
-use std::collections::HashMap;
 
 pub fn shortest_path_tree(graph_matrix: &GraphMatrix, source: usize) -> Option<Vec<ShortestPathTreeNode>> {
-    
+    shortest_path_tree_filtered(graph_matrix, source, |_, _, _| true)
+}
+
+/// Same as [`shortest_path_tree`], but skips the nodes and edges excluded by `options`.
+/// Returns `None` if the source itself is excluded.
+pub fn shortest_path_tree_with_options(
+    graph_matrix: &GraphMatrix,
+    source: usize,
+    options: &QueryOptions<usize>,
+) -> Option<ShortestPathTree> {
+    if !options.allows_node(&source) {
+        return None;
+    }
+
+    shortest_path_tree_filtered(graph_matrix, source, |from, to, distance| {
+        options.allows_edge(&from, &to, distance)
+    })
+}
+
+/// Core of the search, an edge is only followed if `edge_allowed(from, to, distance)` holds
+pub(crate) fn shortest_path_tree_filtered(
+    graph_matrix: &GraphMatrix,
+    source: usize,
+    edge_allowed: impl Fn(usize, usize, u32) -> bool,
+) -> Option<ShortestPathTree> {
     // check if source node exists in the matrix
     let node_count = graph_matrix.len();
     if source >= node_count {
@@ -35,8 +60,8 @@ pub fn shortest_path_tree(graph_matrix: &GraphMatrix, source: usize) -> Option<V
         for index in 0..node_count {
             if !visited[index] {
                 let distance = column[index];
-                if distance > 0 {
-                    let mut record = &mut shortest_path_tree[index];
+                if distance > 0 && edge_allowed(node, index, distance) {
+                    let record = &mut shortest_path_tree[index];
                     let new_distance = node_distance + distance;
                     if record.distance > new_distance {
                         record.distance = new_distance;
@@ -58,34 +83,112 @@ pub fn shortest_path_tree(graph_matrix: &GraphMatrix, source: usize) -> Option<V
         }
     }
 
-    shortest_path_tree.sort_by(|a, b| a.to.cmp(&b.to));
+    shortest_path_tree.sort_by_key(|a| a.to);
 
     Some(shortest_path_tree)
 }
 
-pub fn graph_map_to_matrix(graph_map: &GraphMap) -> (Vec<String>, GraphMatrix) {
-    let node_count = graph_map.len();
+/// Runs one search seeded with all `sources` at distance zero.
+/// The `from` field of each tree node holds its nearest source, which gives a graph Voronoi partition.
+/// Nodes not reachable from any source keep `u32::MAX` as distance and point to themselves.
+pub fn multi_source_shortest_path_tree(
+    graph_matrix: &GraphMatrix,
+    sources: &[usize],
+) -> Option<ShortestPathTree> {
+    // check if all source nodes exist in the matrix
+    let node_count = graph_matrix.len();
+    if sources.is_empty() || sources.iter().any(|&source| source >= node_count) {
+        return None;
+    }
+
+    let mut visited = vec![false; node_count];
+    let mut shortest_path_tree: Vec<ShortestPathTreeNode> = Vec::with_capacity(node_count);
+    for node in 0..node_count {
+        shortest_path_tree.push(ShortestPathTreeNode {
+            from: node,
+            to: node,
+            distance: u32::MAX,
+            previous: node,
+        });
+    }
+
+    // every source starts at zero distance and owns itself
+    for &source in sources {
+        shortest_path_tree[source].distance = 0;
+    }
+
+    loop {
+        // checking for not visited record with min distance
+        let mut node = usize::MAX;
+        let mut min_distance = u32::MAX;
+        for index in 0..node_count {
+            let record = &shortest_path_tree[index];
+            if !visited[index] && record.distance < min_distance {
+                min_distance = record.distance;
+                node = index;
+            }
+        }
+
+        if node == usize::MAX {
+            break;
+        }
+
+        visited[node] = true;
+        let node_source = shortest_path_tree[node].from;
+        let column = &graph_matrix[node];
+
+        // relaxing edges, the nearest source is inherited along with the previous node
+        for index in 0..node_count {
+            let distance = column[index];
+            if !visited[index] && distance > 0 {
+                let record = &mut shortest_path_tree[index];
+                let new_distance = min_distance + distance;
+                if record.distance > new_distance {
+                    record.distance = new_distance;
+                    record.previous = node;
+                    record.from = node_source;
+                }
+            }
+        }
+    }
 
-    let mut names: Vec<String> = graph_map.keys().map(|x| x.clone()).collect();
-    names.sort();
+    Some(shortest_path_tree)
+}
 
-    let mut name_lookup: HashMap<String, usize> = HashMap::with_capacity(node_count);
-    for name in names.iter() {
-        name_lookup.insert(name.clone(), name_lookup.len());
+/// Distance table between every pair of nodes, `u32::MAX` marks unreachable pairs
+pub fn all_pairs_distances(graph_matrix: &GraphMatrix) -> GraphMatrix {
+    let node_count = graph_matrix.len();
+    let mut distances: GraphMatrix = Vec::with_capacity(node_count);
+    let mut context = SearchContext::with_capacity(node_count);
+    for source in 0..node_count {
+        context.search(graph_matrix, source).unwrap();
+        distances.push((0..node_count).map(|x| context.distance(x)).collect());
     }
+    distances
+}
+
+pub fn graph_map_to_matrix(graph_map: &GraphMap) -> (Vec<String>, GraphMatrix) {
+    let graph = graph_map_to_matrix_named(graph_map);
+    (graph.names.into_names(), graph.matrix)
+}
+
+/// Same as [`graph_map_to_matrix`], the graph keeps the node names
+pub fn graph_map_to_matrix_named(graph_map: &GraphMap) -> GraphMatrixNamed {
+    let names = NodeNames::sorted(graph_map.keys());
+    let node_count = names.len();
 
     let mut matrix = vec![vec![0; node_count]; node_count];
 
     for (node1, map) in graph_map {
-        let index1 = name_lookup[node1];
+        let index1 = names.id(node1).unwrap().index();
         for (node2, distance) in map {
-            let index2 = name_lookup[node2];
+            let index2 = names.id(node2).unwrap().index();
             matrix[index1][index2] = *distance;
             matrix[index2][index1] = *distance;
         }
     }
 
-    (names, matrix)
+    GraphMatrixNamed { names, matrix }
 }
 
 #[cfg(test)]
@@ -114,6 +217,94 @@ mod tests {
         graph_matrix_test(graph_samples::sample4);
     }
 
+    #[test]
+    fn graph_matrix_with_options_test() {
+        let (graph_map, _) = graph_samples::sample1();
+        let (names, graph_matrix) = graph_map_to_matrix(&graph_map);
+        let lookup = name_lookup_map(&names);
+
+        // avoiding B leaves A->H->G->F->C as the way to C
+        let options = QueryOptions::new().exclude_node(lookup["B"]);
+        let tree = shortest_path_tree_with_options(&graph_matrix, lookup["A"], &options).unwrap();
+        assert_eq!(tree[lookup["B"]].distance, u32::MAX);
+        assert!(build_shortest_path_from_tree(lookup["B"], &tree).is_none());
+
+        let shortest_path = build_shortest_path_from_tree(lookup["C"], &tree).unwrap();
+        assert_eq!(shortest_path.distance, 14);
+        let path: Vec<&str> = shortest_path.path.iter().map(|x| names[*x].as_str()).collect();
+        assert_eq!(path, ["A", "H", "G", "F", "C"]);
+    }
+
+    #[test]
+    fn all_pairs_distances_test() {
+        let (graph_map, expected_shortest_paths) = graph_samples::sample1();
+        let (names, graph_matrix) = graph_map_to_matrix(&graph_map);
+
+        let distances = all_pairs_distances(&graph_matrix);
+        assert_eq!(distances.len(), names.len());
+        for expected in expected_shortest_paths {
+            let from = names.iter().position(|x| *x == expected.from).unwrap();
+            let to = names.iter().position(|x| *x == expected.to).unwrap();
+            assert_eq!(distances[from][to], expected.distance);
+            assert_eq!(distances[to][from], expected.distance);
+        }
+    }
+
+    #[test]
+    fn multi_source_single_source_test() {
+        let (graph_map, _) = graph_samples::sample1();
+        let (_, graph_matrix) = graph_map_to_matrix(&graph_map);
+
+        let expected = shortest_path_tree(&graph_matrix, 0).unwrap();
+        let tree = multi_source_shortest_path_tree(&graph_matrix, &[0]).unwrap();
+        assert_eq!(tree, expected);
+    }
+
+    #[test]
+    fn multi_source_test() {
+        let (graph_map, _) = graph_samples::sample1();
+        let (names, graph_matrix) = graph_map_to_matrix(&graph_map);
+        let lookup = name_lookup_map(&names);
+
+        // sources A and E
+        let sources = [lookup["A"], lookup["E"]];
+        let tree = multi_source_shortest_path_tree(&graph_matrix, &sources).unwrap();
+        let nearest: Vec<(&str, u32)> = tree
+            .iter()
+            .map(|n| (names[n.from].as_str(), n.distance))
+            .collect();
+        assert_eq!(
+            nearest,
+            [
+                ("A", 0),
+                ("A", 4),
+                ("A", 12),
+                ("E", 9),
+                ("E", 0),
+                ("E", 10),
+                ("A", 9),
+                ("A", 8),
+                ("A", 14)
+            ]
+        );
+
+        let path = build_shortest_path_from_tree(lookup["F"], &tree).unwrap();
+        assert_eq!(path.path, [lookup["E"], lookup["F"]]);
+
+        assert!(multi_source_shortest_path_tree(&graph_matrix, &[]).is_none());
+        assert!(multi_source_shortest_path_tree(&graph_matrix, &[0, names.len()]).is_none());
+    }
+
+    #[test]
+    fn multi_source_unreachable_test() {
+        let graph_matrix = vec![vec![0, 3, 0], vec![3, 0, 0], vec![0, 0, 0]];
+        let tree = multi_source_shortest_path_tree(&graph_matrix, &[0]).unwrap();
+        assert_eq!(tree[1].distance, 3);
+        assert_eq!(tree[2].distance, u32::MAX);
+        assert_eq!(tree[2].from, 2);
+        assert_eq!(tree[2].previous, 2);
+    }
+
     fn graph_matrix_test(fn_test_input: fn() -> (GraphMap, Vec<ShortestPathNamed>)) {
         let (graph_map, expected_shortest_paths_named) = fn_test_input();
         assert!(!graph_map.is_empty());
@@ -1,5 +1,5 @@
 use crate::dijkstra::graph_matrix;
-use crate::dijkstra::utils::*;
+use crate::dijkstra::query::QueryOptions;
 use crate::graph_type::*;
 
 /// Converts map based graph into vector based graph
@@ -21,47 +21,60 @@ pub fn graph_map_to_vector(graph_map: &GraphMap) -> Option<GraphVector> {
 pub fn graph_vector_to_matrix(graph_vector: &GraphVector) -> (Vec<String>, GraphMatrix) {
     let node_count = graph_vector.len();
 
-    let mut names: Vec<String> = graph_vector.iter().map(|x| x.0.clone()).collect();
-    names.sort();
-
-    let name_lookup = name_lookup_map(&names);
+    let names = NodeNames::sorted(graph_vector.iter().map(|x| &x.0));
 
     let mut matrix = vec![vec![0; node_count]; node_count];
 
     for (node1, edges) in graph_vector.iter() {
-        let index1 = name_lookup[node1];
+        let index1 = names.id(node1).unwrap().index();
         for (node2, distance) in edges.iter() {
-            let index2 = name_lookup[node2];
+            let index2 = names.id(node2).unwrap().index();
             matrix[index1][index2] = *distance;
             matrix[index2][index1] = *distance;
         }
     }
 
-    (names, matrix)
+    (names.into_names(), matrix)
 }
 
 pub fn shortest_path_tree(
     graph_vector: &GraphVector,
     source: &str,
 ) -> Option<ShortestPathTreeNamed> {
-    let (node_names, matrix) = graph_vector_to_matrix(&graph_vector);
+    let (node_names, matrix) = graph_vector_to_matrix(graph_vector);
 
-    let position = node_names.iter().position(|x| x == source);
-    if position.is_none() {
-        return None;
-    }
+    let source_index = node_names.iter().position(|x| x == source)?;
+    let shortest_path_tree_nodes = graph_matrix::shortest_path_tree(&matrix, source_index)?;
 
-    let source_index = position.unwrap();
-    let shortest_path_tree_nodes = graph_matrix::shortest_path_tree(&matrix, source_index);
-    if shortest_path_tree_nodes.is_none() {
+    shortest_path_tree_named_from_numbered(&node_names, &shortest_path_tree_nodes)
+}
+
+/// Same as [`shortest_path_tree`], but skips the nodes and edges excluded by `options`.
+/// Returns `None` if the source itself is excluded.
+pub fn shortest_path_tree_with_options(
+    graph_vector: &GraphVector,
+    source: &str,
+    options: &QueryOptions,
+) -> Option<ShortestPathTreeNamed> {
+    if !options.allows_node(&source.to_string()) {
         return None;
     }
 
-    shortest_path_tree_named_from_numbered(&node_names, &shortest_path_tree_nodes.unwrap())
+    let (node_names, matrix) = graph_vector_to_matrix(graph_vector);
+
+    let source_index = node_names.iter().position(|x| x == source)?;
+    let excluded = options.excluded_ids(node_names.len(), |x| node_names.binary_search(x).ok());
+    let shortest_path_tree_nodes =
+        graph_matrix::shortest_path_tree_filtered(&matrix, source_index, |from, to, distance| {
+            excluded.allows_edge(from, to)
+                && options.filter_allows(&node_names[from], &node_names[to], distance)
+        })?;
+
+    shortest_path_tree_named_from_numbered(&node_names, &shortest_path_tree_nodes)
 }
 
 pub fn shortest_path_tree_named_from_numbered(
-    node_names: &Vec<String>,
+    node_names: &[String],
     shortest_path_tree: &ShortestPathTree,
 ) -> Option<ShortestPathTreeNamed> {
     if shortest_path_tree.is_empty() {
@@ -85,6 +98,7 @@ pub fn shortest_path_tree_named_from_numbered(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dijkstra::utils::*;
     use crate::graph_samples;
 
     #[test]
@@ -134,4 +148,19 @@ mod tests {
             assert_eq!(shortest_path.unwrap(), expected_shortest_path);
         }
     }
+
+    #[test]
+    fn graph_vector_with_options_test() {
+        let (graph_map, _) = graph_samples::sample4();
+        let graph_vector = graph_map_to_vector(&graph_map).unwrap();
+
+        let options = QueryOptions::new().exclude_edge("C".to_string(), "F".to_string());
+        let tree = shortest_path_tree_with_options(&graph_vector, "A", &options).unwrap();
+        let shortest_path = build_shortest_path_from_tree_named("E", &tree).unwrap();
+        assert_eq!(shortest_path.distance, 23);
+        assert_eq!(shortest_path.path, ["A", "F", "E"]);
+
+        let options = QueryOptions::new().exclude_node("A".to_string());
+        assert!(shortest_path_tree_with_options(&graph_vector, "A", &options).is_none());
+    }
 }
@@ -2,3 +2,16 @@ pub mod graph_map;
 pub mod graph_vector;
 pub mod graph_matrix;
 pub mod utils;
+pub mod shortest_path_dag;
+pub mod via;
+pub mod query;
+pub mod bounded;
+pub mod nearest;
+pub mod alternatives;
+pub mod replacement;
+pub mod dynamic;
+pub mod engine;
+pub mod workspace;
+pub mod parallel;
+pub mod delta_stepping;
+pub mod graph_csr;
@@ -0,0 +1,155 @@
+// k-nearest tagged targets
+// Runs Dijkstra from the source and stops as soon as `k` target nodes are settled,
+// e.g. the 3 nearest ambulances to an incident node.
+
+use crate::graph_type::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Returns up to `k` nearest nodes accepted by `is_target`, in distance order.
+/// Fewer paths are returned if not enough targets are reachable,
+/// nodes whose distance does not fit below `u32::MAX` count as unreachable.
+pub fn k_nearest_targets(
+    graph_map: &GraphMap,
+    source: &str,
+    k: usize,
+    is_target: impl Fn(&str) -> bool,
+) -> Option<Vec<ShortestPathNamed>> {
+    if !graph_map.contains_key(source) {
+        return None;
+    }
+
+    let mut settled: HashMap<&str, (u32, &str)> = HashMap::new();
+    let mut heap: BinaryHeap<Reverse<(u32, &str, &str)>> = BinaryHeap::new();
+    heap.push(Reverse((0, source, source)));
+
+    let mut targets: Vec<&str> = Vec::with_capacity(k.min(graph_map.len()));
+    while let Some(Reverse((distance, node, previous))) = heap.pop() {
+        if targets.len() == k {
+            break;
+        }
+        if settled.contains_key(node) {
+            continue;
+        }
+        settled.insert(node, (distance, previous));
+
+        // targets do not block the search, a shortest path may pass through one
+        if is_target(node) {
+            targets.push(node);
+        }
+
+        if let Some(edges) = graph_map.get(node) {
+            for (next, edge_distance) in edges {
+                if settled.contains_key(next.as_str()) {
+                    continue;
+                }
+                match distance.checked_add(*edge_distance) {
+                    Some(next_distance) if next_distance != u32::MAX => {
+                        heap.push(Reverse((next_distance, next, node)))
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let shortest_paths = targets
+        .into_iter()
+        .map(|target| {
+            let mut path: Vec<String> = vec![target.to_string()];
+            let mut node = target;
+            while node != source {
+                node = settled[node].1;
+                path.push(node.to_string());
+            }
+            if path.len() == 1 {
+                path.push(source.to_string());
+            }
+            path.reverse();
+
+            ShortestPathNamed {
+                from: source.to_string(),
+                to: target.to_string(),
+                distance: settled[target].0,
+                path,
+            }
+        })
+        .collect();
+
+    Some(shortest_paths)
+}
+
+/// Same as [`k_nearest_targets`] with the targets given as a set of node names
+pub fn k_nearest_in_set(
+    graph_map: &GraphMap,
+    source: &str,
+    k: usize,
+    targets: &HashSet<String>,
+) -> Option<Vec<ShortestPathNamed>> {
+    k_nearest_targets(graph_map, source, k, |node| targets.contains(node))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::graph_map;
+    use crate::graph_samples;
+
+    #[test]
+    fn k_nearest_in_set_test() {
+        let (graph, _) = graph_samples::sample1();
+        let targets: HashSet<String> = ["C", "E", "G", "I"].iter().map(|x| x.to_string()).collect();
+
+        let nearest = k_nearest_in_set(&graph, "A", 3, &targets).unwrap();
+        let found: Vec<(&str, u32)> = nearest
+            .iter()
+            .map(|x| (x.to.as_str(), x.distance))
+            .collect();
+        assert_eq!(found, [("G", 9), ("C", 12), ("I", 14)]);
+
+        for shortest_path in nearest {
+            let expected = graph_map::shortest_path(&graph, "A", &shortest_path.to).unwrap();
+            assert_eq!(shortest_path, expected);
+        }
+    }
+
+    #[test]
+    fn k_nearest_targets_test() {
+        let (graph, _) = graph_samples::sample1();
+
+        // the source itself can be a target
+        let nearest = k_nearest_targets(&graph, "A", 10, |x| x == "A" || x == "E").unwrap();
+        assert_eq!(nearest.len(), 2);
+        assert_eq!(nearest[0].path, ["A", "A"]);
+        assert_eq!(nearest[1].path, ["A", "H", "G", "F", "E"]);
+
+        assert!(k_nearest_targets(&graph, "A", 0, |_| true)
+            .unwrap()
+            .is_empty());
+        assert!(k_nearest_targets(&graph, "X", 1, |_| true).is_none());
+
+        // an unbounded k returns every reachable target
+        let nearest = k_nearest_targets(&graph, "A", usize::MAX, |_| true).unwrap();
+        assert_eq!(nearest.len(), graph.len());
+    }
+
+    #[test]
+    fn k_nearest_overflow_test() {
+        let mut graph = GraphMap::new();
+        graph.insert(
+            "A".to_string(),
+            HashMap::from([("B".to_string(), 1 << 31), ("D".to_string(), u32::MAX)]),
+        );
+        graph.insert("B".to_string(), HashMap::from([("C".to_string(), 1 << 31)]));
+        graph.insert("C".to_string(), HashMap::new());
+        graph.insert("D".to_string(), HashMap::new());
+
+        // C lies 2^32 away and D at u32::MAX, neither is reachable
+        let nearest = k_nearest_targets(&graph, "A", 4, |_| true).unwrap();
+        let found: Vec<(&str, u32)> = nearest
+            .iter()
+            .map(|x| (x.to.as_str(), x.distance))
+            .collect();
+        assert_eq!(found, [("A", 0), ("B", 1 << 31)]);
+    }
+}
@@ -0,0 +1,246 @@
+// Parallel batch of shortest path trees
+// Map based graphs are searched on their CSR form, which keeps the edge direction.
+// The sources are shared out to scoped worker threads through an atomic counter,
+// every worker keeps its own `SearchContext` and the read-only graph is borrowed by all of them.
+// Results are put back in the order of the given sources, whatever the finishing order.
+
+use crate::dijkstra::graph_csr;
+use crate::dijkstra::workspace::SearchContext;
+use crate::graph_type::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Progress callback receiving `(finished, total)`, called from the worker threads
+pub type ProgressCallback<'a> = Box<dyn Fn(usize, usize) + Sync + 'a>;
+
+#[derive(Default)]
+pub struct ParallelOptions<'a> {
+    /// Number of worker threads, all available cores by default
+    pub threads: Option<usize>,
+    pub progress: Option<ProgressCallback<'a>>,
+}
+
+/// Runs `search` for every source index on the worker threads, results in source order
+fn run_parallel<T: Send>(
+    count: usize,
+    options: &ParallelOptions,
+    search: impl Fn(&mut SearchContext, usize) -> T + Sync,
+) -> Vec<T> {
+    let threads = options
+        .threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |x| x.get()))
+        .clamp(1, count.max(1));
+    let next = AtomicUsize::new(0);
+    let finished = AtomicUsize::new(0);
+
+    let mut results: Vec<(usize, T)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut context = SearchContext::new();
+                    let mut results: Vec<(usize, T)> = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= count {
+                            break;
+                        }
+                        results.push((index, search(&mut context, index)));
+
+                        let done = finished.fetch_add(1, Ordering::Relaxed) + 1;
+                        if let Some(progress) = &options.progress {
+                            progress(done, count);
+                        }
+                    }
+                    results
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+
+    results.sort_by_key(|x| x.0);
+    results.into_iter().map(|x| x.1).collect()
+}
+
+/// Shortest path trees of all `sources`, in the same order.
+/// Returns `None` if a source is not in the matrix.
+pub fn shortest_path_trees(
+    graph_matrix: &GraphMatrix,
+    sources: &[usize],
+    options: &ParallelOptions,
+) -> Option<Vec<ShortestPathTree>> {
+    let node_count = graph_matrix.len();
+    if sources.iter().any(|&source| source >= node_count) {
+        return None;
+    }
+
+    Some(run_parallel(sources.len(), options, |context, index| {
+        let mut tree: ShortestPathTree = Vec::with_capacity(node_count);
+        context.search(graph_matrix, sources[index]).unwrap();
+        context.write_tree(node_count, &mut tree);
+        tree
+    }))
+}
+
+/// Same as [`shortest_path_trees`] on a CSR graph, edges keep their direction.
+/// The memory stays linear in the edge count, so sparse graphs with many nodes fit.
+pub fn shortest_path_trees_csr(
+    graph_csr: &(impl CsrGraph + Sync),
+    sources: &[usize],
+    options: &ParallelOptions,
+) -> Option<Vec<ShortestPathTree>> {
+    let node_count = graph_csr.node_count();
+    if sources.iter().any(|&source| source >= node_count) {
+        return None;
+    }
+
+    Some(run_parallel(sources.len(), options, |context, index| {
+        let mut tree: ShortestPathTree = Vec::with_capacity(node_count);
+        context.search_csr(graph_csr, sources[index]).unwrap();
+        context.write_tree(node_count, &mut tree);
+        tree
+    }))
+}
+
+/// Parallel version of `graph_map::shortest_path_tree` for many sources, run on the CSR form
+pub fn shortest_path_trees_named(
+    graph_map: &GraphMap,
+    sources: &[&str],
+    options: &ParallelOptions,
+) -> Option<Vec<ShortestPathTreeNamed>> {
+    let graph = graph_csr::graph_map_to_csr_named(graph_map);
+    let mut source_ids: Vec<usize> = Vec::with_capacity(sources.len());
+    for source in sources {
+        source_ids.push(graph.names.id(source)?.index());
+    }
+
+    let trees = shortest_path_trees_csr(&graph.csr, &source_ids, options)?;
+    Some(
+        trees
+            .iter()
+            .map(|tree| graph.names.resolve_tree(tree))
+            .collect(),
+    )
+}
+
+/// Parallel version of `graph_matrix::all_pairs_distances`
+pub fn all_pairs_distances(graph_matrix: &GraphMatrix, options: &ParallelOptions) -> GraphMatrix {
+    let node_count = graph_matrix.len();
+    run_parallel(node_count, options, |context, source| {
+        context.search(graph_matrix, source).unwrap();
+        (0..node_count).map(|x| context.distance(x)).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::{graph_map, graph_matrix};
+    use crate::graph_samples;
+    use crate::random_graphs::random_graph_map;
+    use std::sync::Mutex;
+
+    #[test]
+    fn shortest_path_trees_test() {
+        let (graph, _) = graph_samples::sample1();
+        let (_, matrix) = graph_matrix::graph_map_to_matrix(&graph);
+        let sources: Vec<usize> = (0..matrix.len()).rev().chain([0, 0]).collect();
+
+        let progress: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+        let options = ParallelOptions {
+            threads: Some(3),
+            progress: Some(Box::new(|done, total| {
+                assert_eq!(total, sources.len());
+                progress.lock().unwrap().push(done);
+            })),
+        };
+        let trees = shortest_path_trees(&matrix, &sources, &options).unwrap();
+        for (tree, &source) in trees.iter().zip(sources.iter()) {
+            assert_eq!(
+                tree,
+                &graph_matrix::shortest_path_tree(&matrix, source).unwrap()
+            );
+        }
+
+        drop(options);
+        let mut progress = progress.into_inner().unwrap();
+        progress.sort();
+        assert_eq!(progress, (1..=sources.len()).collect::<Vec<_>>());
+
+        assert!(shortest_path_trees(&matrix, &[0, 99], &ParallelOptions::default()).is_none());
+        assert!(
+            shortest_path_trees(&matrix, &[], &ParallelOptions::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn shortest_path_trees_named_test() {
+        let (graph, _) = graph_samples::sample2();
+        let sources = ["E", "A", "C"];
+
+        let trees =
+            shortest_path_trees_named(&graph, &sources, &ParallelOptions::default()).unwrap();
+        for (tree, source) in trees.iter().zip(sources) {
+            assert_eq!(
+                tree,
+                &graph_map::shortest_path_tree(&graph, source).unwrap()
+            );
+        }
+
+        assert!(
+            shortest_path_trees_named(&graph, &["A", "X"], &ParallelOptions::default()).is_none()
+        );
+    }
+
+    #[test]
+    fn shortest_path_trees_directed_test() {
+        // one-way edges, the trees differ from those of the symmetric matrix
+        let mut seed = 17;
+        let graph = random_graph_map(40, 6, 20, &mut seed);
+        let names: Vec<String> = (0..40).step_by(3).map(|x| format!("N{:03}", x)).collect();
+        let sources: Vec<&str> = names.iter().map(|x| x.as_str()).collect();
+
+        let options = ParallelOptions {
+            threads: Some(4),
+            ..Default::default()
+        };
+        let trees = shortest_path_trees_named(&graph, &sources, &options).unwrap();
+        for (tree, source) in trees.iter().zip(sources) {
+            assert_eq!(
+                tree,
+                &graph_map::shortest_path_tree(&graph, source).unwrap()
+            );
+        }
+
+        let (_, graph_csr) = graph_csr::graph_map_to_csr(&graph);
+        let trees = shortest_path_trees_csr(&graph_csr, &[5, 0], &options).unwrap();
+        assert_eq!(
+            trees[0],
+            graph_csr::shortest_path_tree(&graph_csr, 5).unwrap()
+        );
+        assert!(shortest_path_trees_csr(&graph_csr, &[40], &options).is_none());
+    }
+
+    #[test]
+    fn all_pairs_distances_test() {
+        let (graph, _) = graph_samples::sample3();
+        let (_, matrix) = graph_matrix::graph_map_to_matrix(&graph);
+
+        for threads in [1, 2, 8] {
+            let options = ParallelOptions {
+                threads: Some(threads),
+                ..Default::default()
+            };
+            assert_eq!(
+                all_pairs_distances(&matrix, &options),
+                graph_matrix::all_pairs_distances(&matrix)
+            );
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+/// Edge predicate receiving `(from, to, distance)`
+pub type EdgeFilter<'a, N> = Box<dyn Fn(&N, &N, u32) -> bool + 'a>;
+
+/// Excluded nodes and edges of a [`QueryOptions`] as node ids, resolved once before a search
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExcludedIds {
+    nodes: Vec<bool>,
+    /// Both directions of every excluded edge
+    edges: HashSet<(usize, usize)>,
+}
+
+impl ExcludedIds {
+    pub fn allows_node(&self, node: usize) -> bool {
+        !self.nodes.get(node).copied().unwrap_or(false)
+    }
+
+    pub fn allows_edge(&self, from: usize, to: usize) -> bool {
+        self.allows_node(from)
+            && self.allows_node(to)
+            && (self.edges.is_empty() || !self.edges.contains(&(from, to)))
+    }
+}
+
+/// Per-query restrictions applied on top of a graph without mutating it.
+/// `N` is the node type: `String` for named graphs, `usize` for `GraphMatrix`.
+/// Excluded edges are blocked in both directions.
+pub struct QueryOptions<'a, N = String> {
+    pub excluded_nodes: HashSet<N>,
+    pub excluded_edges: HashSet<(N, N)>,
+    /// Called with `(from, to, distance)` in travel direction, returning false skips the edge
+    pub edge_filter: Option<EdgeFilter<'a, N>>,
+}
+
+impl<N> Default for QueryOptions<'_, N> {
+    fn default() -> Self {
+        QueryOptions {
+            excluded_nodes: HashSet::new(),
+            excluded_edges: HashSet::new(),
+            edge_filter: None,
+        }
+    }
+}
+
+impl<'a, N: Eq + Hash + Clone> QueryOptions<'a, N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn exclude_node(mut self, node: N) -> Self {
+        self.excluded_nodes.insert(node);
+        self
+    }
+
+    pub fn exclude_edge(mut self, from: N, to: N) -> Self {
+        self.excluded_edges.insert((from, to));
+        self
+    }
+
+    pub fn edge_filter(mut self, filter: impl Fn(&N, &N, u32) -> bool + 'a) -> Self {
+        self.edge_filter = Some(Box::new(filter));
+        self
+    }
+
+    pub fn allows_node(&self, node: &N) -> bool {
+        !self.excluded_nodes.contains(node)
+    }
+
+    /// Checks a single edge, cloning its ends for the excluded edge lookup.
+    /// Searches resolve the exclusions once with [`QueryOptions::excluded_ids`] instead.
+    pub fn allows_edge(&self, from: &N, to: &N, distance: u32) -> bool {
+        if !self.allows_node(from) || !self.allows_node(to) {
+            return false;
+        }
+
+        if !self.excluded_edges.is_empty() {
+            let edge = (from.clone(), to.clone());
+            let reverse_edge = (to.clone(), from.clone());
+            if self.excluded_edges.contains(&edge) || self.excluded_edges.contains(&reverse_edge) {
+                return false;
+            }
+        }
+
+        self.filter_allows(from, to, distance)
+    }
+
+    /// Only the edge filter, true without one
+    pub fn filter_allows(&self, from: &N, to: &N, distance: u32) -> bool {
+        match &self.edge_filter {
+            Some(filter) => filter(from, to, distance),
+            None => true,
+        }
+    }
+
+    /// Excluded nodes and edges as ids of a graph with `node_count` nodes,
+    /// nodes without an id are not in the graph and are left out
+    pub fn excluded_ids(&self, node_count: usize, id: impl Fn(&N) -> Option<usize>) -> ExcludedIds {
+        let mut excluded = ExcludedIds {
+            nodes: vec![false; node_count],
+            edges: HashSet::with_capacity(self.excluded_edges.len() * 2),
+        };
+        for node in self.excluded_nodes.iter().filter_map(&id) {
+            excluded.nodes[node] = true;
+        }
+        for (from, to) in self.excluded_edges.iter() {
+            if let (Some(from), Some(to)) = (id(from), id(to)) {
+                excluded.edges.insert((from, to));
+                excluded.edges.insert((to, from));
+            }
+        }
+        excluded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_options_test() {
+        let options: QueryOptions<&str> = QueryOptions::new()
+            .exclude_node("H")
+            .exclude_edge("C", "F")
+            .edge_filter(|_, _, distance| distance < 10);
+
+        assert!(!options.allows_node(&"H"));
+        assert!(options.allows_node(&"A"));
+        assert!(!options.allows_edge(&"A", &"H", 8));
+        assert!(!options.allows_edge(&"C", &"F", 3));
+        assert!(!options.allows_edge(&"F", &"C", 3));
+        assert!(!options.allows_edge(&"D", &"F", 14));
+        assert!(options.allows_edge(&"A", &"B", 4));
+
+        let default_options: QueryOptions<usize> = QueryOptions::default();
+        assert!(default_options.allows_edge(&0, &1, u32::MAX));
+
+        // the same exclusions on ids, unknown nodes are dropped
+        let names = ["A", "C", "F", "H"];
+        let excluded =
+            options.excluded_ids(names.len(), |name| names.iter().position(|x| x == name));
+        assert!(!excluded.allows_node(3));
+        assert!(!excluded.allows_edge(0, 3));
+        assert!(!excluded.allows_edge(1, 2));
+        assert!(!excluded.allows_edge(2, 1));
+        assert!(excluded.allows_edge(0, 1));
+        assert!(options.filter_allows(&"A", &"H", 8));
+        assert!(!options.filter_allows(&"D", &"F", 14));
+    }
+}
@@ -0,0 +1,297 @@
+// Replacement paths and most vital edge
+//
+// Instead of one search per removed edge, two shortest path trees are used (from the start
+// and from the end of the path) and every graph edge is checked once (Malik, Mittal and Gupta):
+// - a node is labelled with the index where its tree path leaves the shortest path,
+// - an edge (u, v) can bypass path edges `label_s(u)..label_t(v)`,
+//   with length `distance_s(u) + weight + distance_t(v)`,
+// - candidates are sorted by length and each path edge takes the first one covering it.
+// Graphs are undirected, as in `graph_map_to_matrix`.
+
+use crate::dijkstra::graph_matrix;
+use crate::graph_type::*;
+
+/// Parent of every node in a shortest path tree, with the nodes of `path` forced to follow it.
+/// Returns `None` if `path` is not a shortest path of the tree.
+fn tree_parents(
+    tree: &ShortestPathTree,
+    graph_matrix: &GraphMatrix,
+    path: &[usize],
+) -> Option<Vec<usize>> {
+    let mut parents: Vec<usize> = tree.iter().map(|x| x.previous).collect();
+    for pair in path.windows(2) {
+        let weight = graph_matrix[pair[0]][pair[1]];
+        if weight == 0 || tree[pair[0]].distance + weight != tree[pair[1]].distance {
+            return None;
+        }
+        parents[pair[1]] = pair[0];
+    }
+    Some(parents)
+}
+
+/// Index in `path` where the tree path of each node joins it, `usize::MAX` for unreachable nodes
+fn path_labels(tree: &ShortestPathTree, parents: &[usize], path: &[usize]) -> Vec<usize> {
+    let mut labels = vec![usize::MAX; tree.len()];
+    for (index, &node) in path.iter().enumerate() {
+        labels[node] = index;
+    }
+
+    // parents are closer to the root, so they are labelled first
+    let mut order: Vec<usize> = (0..tree.len())
+        .filter(|&node| tree[node].distance != u32::MAX)
+        .collect();
+    order.sort_by_key(|&node| tree[node].distance);
+    for node in order {
+        if labels[node] == usize::MAX {
+            labels[node] = labels[parents[node]];
+        }
+    }
+    labels
+}
+
+/// First index not covered yet at or after `index`, with path compression
+fn next_uncovered(next: &mut [usize], mut index: usize) -> usize {
+    let mut root = index;
+    while next[root] != root {
+        root = next[root];
+    }
+    while next[index] != root {
+        let following = next[index];
+        next[index] = root;
+        index = following;
+    }
+    root
+}
+
+/// Walks the parents from `node` up to the tree root
+fn walk_to_root(parents: &[usize], mut node: usize) -> Vec<usize> {
+    let mut nodes = vec![node];
+    while parents[node] != node {
+        node = parents[node];
+        nodes.push(node);
+    }
+    nodes
+}
+
+/// Best detour for every edge of `shortest_path`, in path order
+pub fn replacement_paths(
+    graph_matrix: &GraphMatrix,
+    shortest_path: &ShortestPath,
+) -> Option<Vec<ReplacementPath>> {
+    let path = &shortest_path.path;
+    if path.first() != Some(&shortest_path.from) || path.last() != Some(&shortest_path.to) {
+        return None;
+    }
+    if shortest_path.from == shortest_path.to {
+        return Some(Vec::new());
+    }
+
+    let node_count = graph_matrix.len();
+    let tree_s = graph_matrix::shortest_path_tree(graph_matrix, shortest_path.from)?;
+    let tree_t = graph_matrix::shortest_path_tree(graph_matrix, shortest_path.to)?;
+
+    let reversed: Vec<usize> = path.iter().rev().copied().collect();
+    let parents_s = tree_parents(&tree_s, graph_matrix, path)?;
+    let parents_t = tree_parents(&tree_t, graph_matrix, &reversed)?;
+
+    let labels_s = path_labels(&tree_s, &parents_s, path);
+    let mut labels_t = path_labels(&tree_t, &parents_t, &reversed);
+    let edge_count = path.len() - 1;
+    for label in labels_t.iter_mut().filter(|x| **x != usize::MAX) {
+        *label = edge_count - *label;
+    }
+
+    let mut position = vec![usize::MAX; node_count];
+    for (index, &node) in path.iter().enumerate() {
+        position[node] = index;
+    }
+
+    // candidate (length, u, v) bypassing path edges label_s(u)..label_t(v)
+    let mut candidates: Vec<(u64, usize, usize)> = Vec::new();
+    for u in 0..node_count {
+        for v in 0..node_count {
+            let weight = graph_matrix[u][v];
+            if weight == 0 || labels_s[u] == usize::MAX || labels_t[v] == usize::MAX {
+                continue;
+            }
+            if labels_s[u] >= labels_t[v] {
+                continue;
+            }
+            // the path edges themselves are the ones being removed
+            if position[u] != usize::MAX && position[v] == position[u] + 1 {
+                continue;
+            }
+
+            let length = tree_s[u].distance as u64 + weight as u64 + tree_t[v].distance as u64;
+            candidates.push((length, u, v));
+        }
+    }
+    candidates.sort();
+
+    // each path edge takes the shortest candidate covering it, skipping already covered ones
+    let mut best: Vec<Option<(u64, usize, usize)>> = vec![None; edge_count];
+    let mut next: Vec<usize> = (0..=edge_count).collect();
+    for candidate in candidates {
+        let (_, u, v) = candidate;
+        let mut index = next_uncovered(&mut next, labels_s[u]);
+        while index < labels_t[v] {
+            best[index] = Some(candidate);
+            next[index] = index + 1;
+            index = next_uncovered(&mut next, index);
+        }
+    }
+
+    let replacements = path
+        .windows(2)
+        .enumerate()
+        .map(|(index, pair)| {
+            let detour = best[index].map(|(length, u, v)| {
+                let mut detour_path = walk_to_root(&parents_s, u);
+                detour_path.reverse();
+                detour_path.extend(walk_to_root(&parents_t, v));
+                ShortestPath {
+                    from: shortest_path.from,
+                    to: shortest_path.to,
+                    distance: length as u32,
+                    path: detour_path,
+                }
+            });
+            ReplacementPath {
+                from: pair[0],
+                to: pair[1],
+                increase: detour
+                    .as_ref()
+                    .map_or(u32::MAX, |x| x.distance - tree_s[shortest_path.to].distance),
+                detour,
+            }
+        })
+        .collect();
+
+    Some(replacements)
+}
+
+/// Named version of [`replacement_paths`] for map based graphs
+pub fn replacement_paths_named(
+    graph_map: &GraphMap,
+    shortest_path: &ShortestPathNamed,
+) -> Option<Vec<ReplacementPathNamed>> {
+    let GraphMatrixNamed { names, matrix } = graph_matrix::graph_map_to_matrix_named(graph_map);
+    let mut path: Vec<usize> = Vec::with_capacity(shortest_path.path.len());
+    for name in shortest_path.path.iter() {
+        path.push(names.id(name)?.index());
+    }
+    let numbered = ShortestPath {
+        from: names.id(&shortest_path.from)?.index(),
+        to: names.id(&shortest_path.to)?.index(),
+        distance: shortest_path.distance,
+        path,
+    };
+    let replacements = replacement_paths(&matrix, &numbered)?;
+
+    Some(
+        replacements
+            .into_iter()
+            .map(|x| ReplacementPathNamed {
+                from: names.names()[x.from].clone(),
+                to: names.names()[x.to].clone(),
+                detour: x.detour.map(|detour| names.resolve_path(&detour)),
+                increase: x.increase,
+            })
+            .collect(),
+    )
+}
+
+/// The path edge whose failure costs the most, an edge without detour wins over all others
+pub fn most_vital_edge(replacements: &[ReplacementPathNamed]) -> Option<&ReplacementPathNamed> {
+    replacements.iter().max_by_key(|x| x.increase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::graph_map;
+    use crate::dijkstra::utils::build_shortest_path_from_tree;
+    use crate::graph_samples;
+    use crate::random_graphs::random_undirected_graph_matrix;
+
+    #[test]
+    fn replacement_paths_sample1_test() {
+        let (graph, _) = graph_samples::sample1();
+        let shortest_path = graph_map::shortest_path(&graph, "A", "D").unwrap();
+
+        let replacements = replacement_paths_named(&graph, &shortest_path).unwrap();
+        let increases: Vec<(&str, &str, u32)> = replacements
+            .iter()
+            .map(|x| (x.from.as_str(), x.to.as_str(), x.increase))
+            .collect();
+        // A-B: A-H-G-F-C-D = 21, B-C: A-H-G-F-C-D = 21, C-D: A-H-G-F-D = 25
+        assert_eq!(increases, [("A", "B", 2), ("B", "C", 2), ("C", "D", 6)]);
+
+        let detour = replacements[2].detour.as_ref().unwrap();
+        assert_eq!(detour.path, ["A", "H", "G", "F", "D"]);
+
+        let vital = most_vital_edge(&replacements).unwrap();
+        assert_eq!((vital.from.as_str(), vital.to.as_str()), ("C", "D"));
+    }
+
+    #[test]
+    fn replacement_paths_bridge_test() {
+        // A-B-C with a detour only around B-C
+        let graph_matrix = vec![
+            vec![0, 1, 0, 0],
+            vec![1, 0, 1, 2],
+            vec![0, 1, 0, 2],
+            vec![0, 2, 2, 0],
+        ];
+        let tree = graph_matrix::shortest_path_tree(&graph_matrix, 0).unwrap();
+        let shortest_path = build_shortest_path_from_tree(2, &tree).unwrap();
+
+        let replacements = replacement_paths(&graph_matrix, &shortest_path).unwrap();
+        assert!(replacements[0].detour.is_none());
+        assert_eq!(replacements[0].increase, u32::MAX);
+        assert_eq!(replacements[1].increase, 3);
+        assert_eq!(replacements[1].detour.as_ref().unwrap().path, [0, 1, 3, 2]);
+    }
+
+    #[test]
+    fn replacement_paths_random_test() {
+        let mut seed = 42;
+        for _ in 0..40 {
+            let node_count = 12;
+            let graph_matrix = random_undirected_graph_matrix(node_count, 30, 9, &mut seed);
+            let tree = graph_matrix::shortest_path_tree(&graph_matrix, 0).unwrap();
+            let Some(shortest_path) = build_shortest_path_from_tree(node_count - 1, &tree) else {
+                continue;
+            };
+
+            let replacements = replacement_paths(&graph_matrix, &shortest_path).unwrap();
+            assert_eq!(replacements.len(), shortest_path.path.len() - 1);
+
+            // comparing with a fresh search on the graph without the edge
+            for replacement in replacements {
+                let mut removed = graph_matrix.clone();
+                removed[replacement.from][replacement.to] = 0;
+                removed[replacement.to][replacement.from] = 0;
+                let expected =
+                    graph_matrix::shortest_path_tree(&removed, 0).unwrap()[node_count - 1].distance;
+
+                match replacement.detour {
+                    Some(detour) => {
+                        assert_eq!(detour.distance, expected);
+                        let length: u32 = detour
+                            .path
+                            .windows(2)
+                            .map(|pair| removed[pair[0]][pair[1]])
+                            .sum();
+                        assert_eq!(length, expected);
+                        assert!(detour
+                            .path
+                            .windows(2)
+                            .all(|pair| removed[pair[0]][pair[1]] > 0));
+                    }
+                    None => assert_eq!(expected, u32::MAX),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,451 @@
+// Equal-cost shortest paths
+// Keeps every tight predecessor of a node instead of a single `previous`,
+// counts the shortest paths and enumerates them lazily (useful for ECMP load balancing).
+// The matrix version follows `graph_matrix`, the CSR version keeps edge directions as `graph_csr` does.
+
+use crate::dijkstra::graph_csr;
+use crate::graph_type::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Builds the shortest-path DAG from `source`.
+/// Unreachable nodes keep `u32::MAX` as distance, no predecessors and a zero path count.
+pub fn shortest_path_dag(graph_matrix: &GraphMatrix, source: usize) -> Option<ShortestPathDag> {
+    // check if source node exists in the matrix
+    let node_count = graph_matrix.len();
+    if source >= node_count {
+        return None;
+    }
+
+    let mut visited = vec![false; node_count];
+    let mut dag: ShortestPathDag = Vec::with_capacity(node_count);
+    for node in 0..node_count {
+        dag.push(ShortestPathDagNode {
+            from: source,
+            to: node,
+            distance: u32::MAX,
+            previous: Vec::new(),
+            path_count: 0,
+        });
+    }
+
+    dag[source].distance = 0;
+    dag[source].path_count = 1;
+
+    loop {
+        // checking for not visited record with min distance
+        let mut node = usize::MAX;
+        let mut min_distance = u32::MAX;
+        for index in 0..node_count {
+            if !visited[index] && dag[index].distance < min_distance {
+                min_distance = dag[index].distance;
+                node = index;
+            }
+        }
+
+        if node == usize::MAX {
+            break;
+        }
+
+        // the node is settled, so its path count is final
+        visited[node] = true;
+        let node_path_count = dag[node].path_count;
+        let column = &graph_matrix[node];
+
+        for index in 0..node_count {
+            let distance = column[index];
+            if visited[index] || distance == 0 {
+                continue;
+            }
+
+            let record = &mut dag[index];
+            let new_distance = min_distance + distance;
+            if record.distance > new_distance {
+                record.distance = new_distance;
+                record.previous.clear();
+                record.previous.push(node);
+                record.path_count = node_path_count;
+            } else if record.distance == new_distance {
+                record.previous.push(node);
+                record.path_count = record.path_count.saturating_add(node_path_count);
+            }
+        }
+    }
+
+    Some(dag)
+}
+
+/// Same as [`shortest_path_dag`] on a directed CSR graph.
+/// Nodes are settled by (distance, id), so the predecessors keep the order of the matrix version.
+pub fn shortest_path_dag_csr(graph_csr: &impl CsrGraph, source: usize) -> Option<ShortestPathDag> {
+    let node_count = graph_csr.node_count();
+    if source >= node_count {
+        return None;
+    }
+
+    let mut dag: ShortestPathDag = (0..node_count)
+        .map(|node| ShortestPathDagNode {
+            from: source,
+            to: node,
+            distance: u32::MAX,
+            previous: Vec::new(),
+            path_count: 0,
+        })
+        .collect();
+    let mut settled = vec![false; node_count];
+    let mut heap: BinaryHeap<Reverse<(u32, usize)>> = BinaryHeap::from([Reverse((0, source))]);
+    dag[source].distance = 0;
+    dag[source].path_count = 1;
+
+    while let Some(Reverse((distance, node))) = heap.pop() {
+        if settled[node] {
+            continue;
+        }
+        // the node is settled, so its path count is final
+        settled[node] = true;
+        let node_path_count = dag[node].path_count;
+
+        for (to, weight) in graph_csr.edges(node) {
+            let new_distance = distance.saturating_add(weight);
+            if settled[to] || new_distance == u32::MAX {
+                continue;
+            }
+
+            let record = &mut dag[to];
+            if record.distance > new_distance {
+                record.distance = new_distance;
+                record.previous.clear();
+                record.previous.push(node);
+                record.path_count = node_path_count;
+                heap.push(Reverse((new_distance, to)));
+            } else if record.distance == new_distance {
+                record.previous.push(node);
+                record.path_count = record.path_count.saturating_add(node_path_count);
+            }
+        }
+    }
+
+    Some(dag)
+}
+
+/// Builds the shortest-path DAG for a map based graph, edges keep their direction
+pub fn shortest_path_dag_named(graph_map: &GraphMap, source: &str) -> Option<ShortestPathDagNamed> {
+    let graph = graph_csr::graph_map_to_csr_named(graph_map);
+    let dag = shortest_path_dag_csr(&graph.csr, graph.names.id(source)?.index())?;
+    Some(shortest_path_dag_named_from_numbered(
+        graph.names.names(),
+        &dag,
+    ))
+}
+
+pub fn shortest_path_dag_named_from_numbered(
+    node_names: &[String],
+    dag: &ShortestPathDag,
+) -> ShortestPathDagNamed {
+    dag.iter()
+        .map(|node| ShortestPathDagNodeNamed {
+            from: node_names[node.from].clone(),
+            to: node_names[node.to].clone(),
+            distance: node.distance,
+            previous: node.previous.iter().map(|x| node_names[*x].clone()).collect(),
+            path_count: node.path_count,
+        })
+        .collect()
+}
+
+/// Lazily enumerates all equal-cost shortest paths to a target of a [`ShortestPathDag`]
+pub struct AllShortestPaths<'a> {
+    dag: &'a ShortestPathDag,
+    from: usize,
+    to: usize,
+    distance: u32,
+    // partial path walked back from the target, with the index of the next predecessor to try
+    stack: Vec<(usize, usize)>,
+}
+
+impl Iterator for AllShortestPaths<'_> {
+    type Item = ShortestPath;
+
+    fn next(&mut self) -> Option<ShortestPath> {
+        loop {
+            let (node, index) = *self.stack.last()?;
+            if node == self.from {
+                let mut path: Vec<usize> = self.stack.iter().rev().map(|x| x.0).collect();
+                if path.len() == 1 {
+                    // same representation as `build_shortest_path_from_tree` for the source itself
+                    path.push(node);
+                }
+                self.stack.pop();
+                return Some(ShortestPath {
+                    from: self.from,
+                    to: self.to,
+                    distance: self.distance,
+                    path,
+                });
+            }
+
+            let previous = &self.dag[node].previous;
+            if index < previous.len() {
+                self.stack.last_mut().unwrap().1 += 1;
+                self.stack.push((previous[index], 0));
+            } else {
+                self.stack.pop();
+            }
+        }
+    }
+}
+
+/// Returns a lazy iterator over all shortest paths to `to`.
+/// Yields nothing for unreachable targets and `None` for unknown ones.
+pub fn all_shortest_paths(dag: &ShortestPathDag, to: usize) -> Option<AllShortestPaths<'_>> {
+    let node = dag.get(to)?;
+    let stack = if node.path_count > 0 {
+        vec![(to, 0)]
+    } else {
+        Vec::new()
+    };
+
+    Some(AllShortestPaths {
+        dag,
+        from: node.from,
+        to,
+        distance: node.distance,
+        stack,
+    })
+}
+
+/// Named version of [`AllShortestPaths`]
+pub struct AllShortestPathsNamed<'a> {
+    dag: &'a ShortestPathDagNamed,
+    names: NodeNames,
+    inner_from: usize,
+    inner_to: usize,
+    distance: u32,
+    stack: Vec<(usize, usize)>,
+}
+
+impl Iterator for AllShortestPathsNamed<'_> {
+    type Item = ShortestPathNamed;
+
+    fn next(&mut self) -> Option<ShortestPathNamed> {
+        loop {
+            let (node, index) = *self.stack.last()?;
+            if node == self.inner_from {
+                let mut path: Vec<String> = self
+                    .stack
+                    .iter()
+                    .rev()
+                    .map(|x| self.dag[x.0].to.clone())
+                    .collect();
+                if path.len() == 1 {
+                    path.push(path[0].clone());
+                }
+                self.stack.pop();
+                return Some(ShortestPathNamed {
+                    from: self.dag[self.inner_from].to.clone(),
+                    to: self.dag[self.inner_to].to.clone(),
+                    distance: self.distance,
+                    path,
+                });
+            }
+
+            let previous = &self.dag[node].previous;
+            if index < previous.len() {
+                self.stack.last_mut().unwrap().1 += 1;
+                self.stack
+                    .push((self.names.id(&previous[index]).unwrap().index(), 0));
+            } else {
+                self.stack.pop();
+            }
+        }
+    }
+}
+
+/// Returns a lazy iterator over all shortest paths to `to` in a named DAG
+pub fn all_shortest_paths_named<'a>(
+    dag: &'a ShortestPathDagNamed,
+    to: &str,
+) -> Option<AllShortestPathsNamed<'a>> {
+    let names = NodeNames::from_names(dag.iter().map(|x| x.to.clone()).collect());
+
+    let inner_to = names.id(to)?.index();
+    let node = &dag[inner_to];
+    let inner_from = names.id(&node.from)?.index();
+    let stack = if node.path_count > 0 {
+        vec![(inner_to, 0)]
+    } else {
+        Vec::new()
+    };
+
+    Some(AllShortestPathsNamed {
+        dag,
+        names,
+        inner_from,
+        inner_to,
+        distance: node.distance,
+        stack,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::{graph_map, graph_matrix};
+    use crate::graph_samples;
+    use crate::random_graphs::*;
+
+    fn numbered_matrix(node_count: usize, edges: &[(usize, usize, u32)]) -> GraphMatrix {
+        let graph_map = graph_samples::build_numbered_undirected_graph(node_count, edges);
+        graph_matrix::graph_map_to_matrix(&graph_map).1
+    }
+
+    #[test]
+    fn dag_matches_tree_test() {
+        for sample in [
+            graph_samples::sample1,
+            graph_samples::sample2,
+            graph_samples::sample3,
+            graph_samples::sample4,
+        ] {
+            let (graph_map, _) = sample();
+            let (_, matrix) = graph_matrix::graph_map_to_matrix(&graph_map);
+            let tree = graph_matrix::shortest_path_tree(&matrix, 0).unwrap();
+            let dag = shortest_path_dag(&matrix, 0).unwrap();
+
+            for (tree_node, dag_node) in tree.iter().zip(dag.iter()) {
+                assert_eq!(tree_node.distance, dag_node.distance);
+                if tree_node.to != 0 {
+                    assert!(dag_node.previous.contains(&tree_node.previous));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn grid_path_count_test() {
+        // 3x3 grid with unit weights, node = row * 3 + column
+        let mut edges = Vec::new();
+        for row in 0..3 {
+            for column in 0..3 {
+                let node = row * 3 + column;
+                if column < 2 {
+                    edges.push((node, node + 1, 1));
+                }
+                if row < 2 {
+                    edges.push((node, node + 3, 1));
+                }
+            }
+        }
+        let matrix = numbered_matrix(9, &edges);
+
+        let dag = shortest_path_dag(&matrix, 0).unwrap();
+        assert_eq!(dag[8].distance, 4);
+        assert_eq!(dag[8].previous, [5, 7]);
+        assert_eq!(dag[8].path_count, 6);
+        assert_eq!(dag[4].path_count, 2);
+
+        let paths: Vec<ShortestPath> = all_shortest_paths(&dag, 8).unwrap().collect();
+        assert_eq!(paths.len(), 6);
+        for path in paths.iter() {
+            assert_eq!(path.distance, 4);
+            assert_eq!(path.path.len(), 5);
+            assert_eq!(path.path[0], 0);
+            assert_eq!(path.path[4], 8);
+        }
+        for i in 0..paths.len() {
+            for j in i + 1..paths.len() {
+                assert_ne!(paths[i].path, paths[j].path);
+            }
+        }
+
+        let source_paths: Vec<ShortestPath> = all_shortest_paths(&dag, 0).unwrap().collect();
+        assert_eq!(source_paths.len(), 1);
+        assert_eq!(source_paths[0].path, [0, 0]);
+
+        assert!(all_shortest_paths(&dag, 9).is_none());
+    }
+
+    #[test]
+    fn path_count_saturates_test() {
+        // 70 diamonds in a row give 2^70 shortest paths
+        let diamonds = 70;
+        let mut edges = Vec::new();
+        for i in 0..diamonds {
+            let start = i * 3;
+            edges.push((start, start + 1, 1));
+            edges.push((start, start + 2, 1));
+            edges.push((start + 1, start + 3, 1));
+            edges.push((start + 2, start + 3, 1));
+        }
+        let node_count = diamonds * 3 + 1;
+        let matrix = numbered_matrix(node_count, &edges);
+
+        let dag = shortest_path_dag(&matrix, 0).unwrap();
+        assert_eq!(dag[30].path_count, 1 << 10);
+        assert_eq!(dag[node_count - 1].path_count, u64::MAX);
+        assert_eq!(
+            all_shortest_paths(&dag, node_count - 1).unwrap().take(5).count(),
+            5
+        );
+    }
+
+    #[test]
+    fn unreachable_test() {
+        let matrix = numbered_matrix(3, &[(0, 1, 2)]);
+        let dag = shortest_path_dag(&matrix, 0).unwrap();
+        assert_eq!(dag[2].distance, u32::MAX);
+        assert_eq!(dag[2].path_count, 0);
+        assert!(dag[2].previous.is_empty());
+        assert_eq!(all_shortest_paths(&dag, 2).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn dag_csr_test() {
+        let mut seed = 13;
+        for round in 0..10 {
+            // both versions agree on undirected graphs
+            let matrix = random_undirected_graph_matrix(30, 10 + round, 3, &mut seed);
+            let graph_csr = graph_csr::graph_matrix_to_csr(&matrix);
+            assert_eq!(
+                shortest_path_dag_csr(&graph_csr, 0),
+                shortest_path_dag(&matrix, 0)
+            );
+
+            // directed graphs keep the direction of their edges
+            let graph = random_graph_map(30, 5 + round, 3, &mut seed);
+            let dag = shortest_path_dag_named(&graph, "N000").unwrap();
+            let tree = graph_map::shortest_path_tree(&graph, "N000").unwrap();
+            for (dag_node, tree_node) in dag.iter().zip(tree.iter()) {
+                assert_eq!(dag_node.to, tree_node.to);
+                assert_eq!(dag_node.distance, tree_node.distance);
+                if dag_node.distance != u32::MAX && dag_node.to != "N000" {
+                    assert!(dag_node.previous.contains(&tree_node.previous));
+                }
+            }
+        }
+        assert!(shortest_path_dag_csr(&GraphCsr::default(), 0).is_none());
+    }
+
+    #[test]
+    fn named_test() {
+        let (mut graph_map, _) = graph_samples::sample2();
+        // add a second shortest route A->B->C of distance 7
+        graph_map.get_mut("B").unwrap().insert("C".to_string(), 4);
+        graph_map.get_mut("C").unwrap().insert("B".to_string(), 4);
+
+        let dag = shortest_path_dag_named(&graph_map, "A").unwrap();
+        let c = dag.iter().find(|x| x.to == "C").unwrap();
+        assert_eq!(c.distance, 7);
+        assert_eq!(c.path_count, 2);
+
+        let mut paths: Vec<Vec<String>> = all_shortest_paths_named(&dag, "C")
+            .unwrap()
+            .map(|x| x.path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, [vec!["A", "D", "B", "C"], vec!["A", "D", "E", "C"]]);
+
+        assert!(shortest_path_dag_named(&graph_map, "X").is_none());
+        assert!(all_shortest_paths_named(&dag, "X").is_none());
+    }
+}
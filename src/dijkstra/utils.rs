@@ -37,7 +37,11 @@ pub fn build_shortest_path_from_tree_named(
 ) -> Option<ShortestPathNamed> {
     let mut path: Vec<String> = Vec::new();
     let nodes = &shortest_path_tree;
-    let node = nodes.iter().find(|e| e.to == to).unwrap();
+    let node = nodes.iter().find(|e| e.to == to)?;
+    if node.distance == u32::MAX {
+        return None;
+    }
+
     let from = node.from.clone();
     let distance = node.distance;
     let mut previous = node.previous.clone();
@@ -68,7 +72,11 @@ pub fn build_shortest_path_from_tree_named(
 pub fn build_shortest_path_from_tree(to: usize, shortest_path_tree: &Vec<ShortestPathTreeNode>) -> Option<ShortestPath> {
     let mut path: Vec<usize> = Vec::new();
     let nodes = &shortest_path_tree;
-    let node = nodes.iter().find(|e| e.to == to).unwrap();
+    let node = nodes.iter().find(|e| e.to == to)?;
+    if node.distance == u32::MAX {
+        return None;
+    }
+
     let from = node.from;
     let distance = node.distance;
     let mut previous = node.previous;
@@ -101,7 +109,7 @@ pub fn shortest_path_tree_from_named(node_names: &Vec<String>, shortest_path_tre
         return None;
     }
 
-    let name_lookup = name_lookup_map(&node_names);
+    let name_lookup = name_lookup_map(node_names);
 
     let node_count = shortest_path_tree_string.len();
     let mut tree_nodes: Vec<ShortestPathTreeNode> = Vec::with_capacity(node_count);
@@ -119,7 +127,7 @@ pub fn shortest_path_tree_from_named(node_names: &Vec<String>, shortest_path_tre
     Some(tree_nodes)
 }
 
-pub fn build_path_tree_nodes_from_path(shortest_paths: &Vec<ShortestPath>) -> Vec<ShortestPathTreeNode> {
+pub fn build_path_tree_nodes_from_path(shortest_paths: &[ShortestPath]) -> Vec<ShortestPathTreeNode> {
     let mut expected_tree_nodes: Vec<ShortestPathTreeNode> = Vec::with_capacity(shortest_paths.len());
     for shortest_path in shortest_paths.iter() {
         expected_tree_nodes.push(
@@ -134,7 +142,7 @@ pub fn build_path_tree_nodes_from_path(shortest_paths: &Vec<ShortestPath>) -> Ve
     expected_tree_nodes
 }
 
-pub fn build_path_tree_nodes_from_path_named(shortest_paths: &Vec<ShortestPathNamed>) -> Vec<ShortestPathTreeNodeNamed> {
+pub fn build_path_tree_nodes_from_path_named(shortest_paths: &[ShortestPathNamed]) -> Vec<ShortestPathTreeNodeNamed> {
     let mut expected_tree_nodes: Vec<ShortestPathTreeNodeNamed> = Vec::with_capacity(shortest_paths.len());
     for shortest_path in shortest_paths.iter() {
         expected_tree_nodes.push(
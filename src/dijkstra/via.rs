@@ -0,0 +1,143 @@
+// Via-waypoint routing
+// Routes from `from` through an ordered list of waypoints to `to`,
+// one shortest path tree is computed per distinct leg source and reused.
+
+use crate::dijkstra::graph_csr;
+use crate::graph_type::*;
+use std::collections::HashMap;
+
+/// Finds the shortest route visiting `waypoints` in the given order.
+/// Returns `None` if any of the stops does not exist in the graph, a leg has no path
+/// or the total distance does not fit into `u32`.
+pub fn via_shortest_path(
+    graph_map: &GraphMap,
+    from: &str,
+    waypoints: &[&str],
+    to: &str,
+) -> Option<ViaRouteNamed> {
+    let mut stops: Vec<&str> = Vec::with_capacity(waypoints.len() + 2);
+    stops.push(from);
+    stops.extend_from_slice(waypoints);
+    stops.push(to);
+
+    if stops.iter().any(|stop| !graph_map.contains_key(*stop)) {
+        return None;
+    }
+
+    let graph = graph_csr::graph_map_to_csr_named(graph_map);
+    let mut trees: HashMap<&str, ShortestPathTree> = HashMap::new();
+    let mut path: Vec<String> = vec![from.to_string()];
+    let mut legs: Vec<ViaRouteLeg> = Vec::with_capacity(stops.len() - 1);
+    let mut distance: u32 = 0;
+
+    for leg in stops.windows(2) {
+        let (leg_from, leg_to) = (leg[0], leg[1]);
+        let start = path.len() - 1;
+
+        let mut leg_distance = 0;
+        if leg_from != leg_to {
+            if !trees.contains_key(leg_from) {
+                trees.insert(
+                    leg_from,
+                    graph_csr::shortest_path_tree_by_name(&graph, leg_from)?,
+                );
+            }
+            let leg_path = graph
+                .names
+                .view_tree(&trees[leg_from])
+                .shortest_path(leg_to)?;
+
+            // the first node of the leg is the last node of the previous one
+            path.extend(leg_path.path.into_iter().skip(1));
+            leg_distance = leg_path.distance;
+        }
+
+        distance = distance.checked_add(leg_distance)?;
+        legs.push(ViaRouteLeg {
+            from: leg_from.to_string(),
+            to: leg_to.to_string(),
+            distance: leg_distance,
+            start,
+            end: path.len() - 1,
+        });
+    }
+
+    Some(ViaRouteNamed {
+        from: from.to_string(),
+        to: to.to_string(),
+        distance,
+        path,
+        legs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_samples;
+
+    #[test]
+    fn via_shortest_path_test() {
+        let (graph, _) = graph_samples::sample1();
+
+        let route = via_shortest_path(&graph, "A", &["D", "I"], "E").unwrap();
+        assert_eq!(route.distance, 19 + 9 + 15);
+        assert_eq!(route.path, ["A", "B", "C", "D", "C", "I", "C", "F", "E"]);
+
+        let legs: Vec<(u32, usize, usize)> = route
+            .legs
+            .iter()
+            .map(|x| (x.distance, x.start, x.end))
+            .collect();
+        assert_eq!(legs, [(19, 0, 3), (9, 3, 5), (15, 5, 8)]);
+
+        for leg in route.legs.iter() {
+            assert_eq!(route.path[leg.start], leg.from);
+            assert_eq!(route.path[leg.end], leg.to);
+        }
+    }
+
+    #[test]
+    fn via_repeated_waypoint_test() {
+        let (graph, _) = graph_samples::sample2();
+
+        let route = via_shortest_path(&graph, "A", &["C", "C", "A"], "C").unwrap();
+        assert_eq!(route.distance, 21);
+        assert_eq!(route.legs.len(), 4);
+        assert_eq!(route.legs[1].distance, 0);
+        assert_eq!(route.legs[1].start, route.legs[1].end);
+        assert_eq!(route.path.first().unwrap(), "A");
+        assert_eq!(route.path.last().unwrap(), "C");
+
+        let direct = via_shortest_path(&graph, "A", &[], "C").unwrap();
+        assert_eq!(direct.distance, 7);
+        assert_eq!(direct.path, ["A", "D", "E", "C"]);
+
+        assert!(via_shortest_path(&graph, "A", &["X"], "C").is_none());
+    }
+
+    #[test]
+    fn via_unreachable_and_overflow_test() {
+        let mut graph = GraphMap::new();
+        graph.insert(
+            "A".to_string(),
+            HashMap::from([("B".to_string(), u32::MAX - 1)]),
+        );
+        graph.insert(
+            "B".to_string(),
+            HashMap::from([("A".to_string(), u32::MAX - 1)]),
+        );
+        graph.insert("C".to_string(), HashMap::new());
+
+        // C is not connected to the other stops
+        assert!(via_shortest_path(&graph, "A", &["C"], "B").is_none());
+        assert!(via_shortest_path(&graph, "A", &[], "C").is_none());
+
+        // every leg fits, the total does not
+        assert_eq!(
+            via_shortest_path(&graph, "A", &[], "B").unwrap().distance,
+            u32::MAX - 1
+        );
+        assert!(via_shortest_path(&graph, "A", &["B"], "A").is_none());
+    }
+}
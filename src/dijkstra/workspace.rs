@@ -0,0 +1,284 @@
+// Reusable search workspace
+// Batch loops run thousands of searches on the same graph, so the buffers of a search are kept
+// between queries instead of being allocated each time. Resetting is cheap: every entry carries
+// the generation it was written in, and starting a new search just moves to the next generation.
+// Nodes are settled by (distance, index), which gives the same tree as `graph_matrix::shortest_path_tree`.
+// Matrix and CSR graphs share the same buffers, map graphs are searched through their CSR form.
+
+use crate::graph_type::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+#[derive(Debug, Default)]
+pub struct SearchContext {
+    source: usize,
+    generation: u32,
+    reached: Vec<u32>,
+    settled: Vec<u32>,
+    distances: Vec<u32>,
+    previous: Vec<usize>,
+    heap: BinaryHeap<Reverse<(u32, usize)>>,
+}
+
+impl SearchContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(node_count: usize) -> Self {
+        let mut context = Self::default();
+        context.grow(node_count);
+        context
+    }
+
+    fn grow(&mut self, node_count: usize) {
+        if self.reached.len() < node_count {
+            self.reached.resize(node_count, 0);
+            self.settled.resize(node_count, 0);
+            self.distances.resize(node_count, u32::MAX);
+            self.previous.resize(node_count, 0);
+        }
+    }
+
+    /// Starts a new generation, the stamps are only cleared when the counter wraps around
+    fn reset(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            self.reached.fill(0);
+            self.settled.fill(0);
+            self.generation = 1;
+        }
+        self.heap.clear();
+    }
+
+    /// Runs a search from `source`, the results stay in the context until the next search.
+    /// Returns `None` if the source is not in the matrix.
+    pub fn search(&mut self, graph_matrix: &GraphMatrix, source: usize) -> Option<()> {
+        self.run(graph_matrix.len(), source, |node| {
+            graph_matrix[node]
+                .iter()
+                .enumerate()
+                .filter(|x| *x.1 > 0)
+                .map(|(to, &weight)| (to, weight))
+        })
+    }
+
+    /// Same as [`SearchContext::search`] on a CSR graph, edges keep their direction.
+    /// Map based graphs are converted once with `graph_csr::graph_map_to_csr_named`,
+    /// so the queries themselves never touch node names.
+    pub fn search_csr(&mut self, graph_csr: &impl CsrGraph, source: usize) -> Option<()> {
+        self.run(graph_csr.node_count(), source, |node| graph_csr.edges(node))
+    }
+
+    fn run<I: Iterator<Item = (usize, u32)>>(
+        &mut self,
+        node_count: usize,
+        source: usize,
+        edges: impl Fn(usize) -> I,
+    ) -> Option<()> {
+        if source >= node_count {
+            return None;
+        }
+        self.grow(node_count);
+        self.reset();
+        self.source = source;
+
+        let generation = self.generation;
+        self.reached[source] = generation;
+        self.distances[source] = 0;
+        self.previous[source] = source;
+        self.heap.push(Reverse((0, source)));
+
+        while let Some(Reverse((distance, node))) = self.heap.pop() {
+            if self.settled[node] == generation {
+                continue;
+            }
+            self.settled[node] = generation;
+
+            for (to, weight) in edges(node) {
+                if self.settled[to] == generation {
+                    continue;
+                }
+                let new_distance = distance.saturating_add(weight);
+                if self.reached[to] != generation || self.distances[to] > new_distance {
+                    self.reached[to] = generation;
+                    self.distances[to] = new_distance;
+                    self.previous[to] = node;
+                    self.heap.push(Reverse((new_distance, to)));
+                }
+            }
+        }
+
+        Some(())
+    }
+
+    pub fn source(&self) -> usize {
+        self.source
+    }
+
+    /// Distance from the last source, `u32::MAX` if not reached
+    pub fn distance(&self, node: usize) -> u32 {
+        if self.reached.get(node) == Some(&self.generation) {
+            self.distances[node]
+        } else {
+            u32::MAX
+        }
+    }
+
+    /// Previous node on the way from the last source, the node itself if not reached
+    pub fn previous(&self, node: usize) -> usize {
+        if self.reached.get(node) == Some(&self.generation) {
+            self.previous[node]
+        } else {
+            node
+        }
+    }
+
+    /// Writes the path to `to` into `path`, reusing its buffer, and returns the distance.
+    /// Returns `None` if `to` was not reached.
+    pub fn path_to(&self, to: usize, path: &mut Vec<usize>) -> Option<u32> {
+        let distance = self.distance(to);
+        if distance == u32::MAX {
+            return None;
+        }
+
+        path.clear();
+        let mut node = to;
+        path.push(node);
+        while node != self.source {
+            node = self.previous[node];
+            path.push(node);
+        }
+        path.reverse();
+
+        // the source path is rendered as [X, X], as in `build_shortest_path_from_tree`
+        if to == self.source {
+            path.push(to);
+        }
+        Some(distance)
+    }
+
+    /// Named path to `to`, only the names on the path are cloned
+    pub fn path_to_named(&self, names: &NodeNames, to: usize) -> Option<ShortestPathNamed> {
+        let mut path: Vec<usize> = Vec::new();
+        let distance = self.path_to(to, &mut path)?;
+        Some(ShortestPathNamed {
+            from: names.names()[self.source].clone(),
+            to: names.names()[to].clone(),
+            distance,
+            path: path.into_iter().map(|x| names.names()[x].clone()).collect(),
+        })
+    }
+
+    /// Writes the tree of the last search into `shortest_path_tree`, reusing its buffer
+    pub fn write_tree(&self, node_count: usize, shortest_path_tree: &mut ShortestPathTree) {
+        shortest_path_tree.clear();
+        shortest_path_tree.extend((0..node_count).map(|node| ShortestPathTreeNode {
+            from: self.source,
+            to: node,
+            distance: self.distance(node),
+            previous: self.previous(node),
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::utils::*;
+    use crate::dijkstra::{graph_csr, graph_matrix};
+    use crate::graph_samples;
+
+    #[test]
+    fn search_context_test() {
+        let mut context = SearchContext::new();
+        let mut tree: ShortestPathTree = Vec::new();
+        let mut path: Vec<usize> = Vec::new();
+
+        for (graph, _) in [
+            graph_samples::sample1(),
+            graph_samples::sample2(),
+            graph_samples::sample3(),
+            graph_samples::sample4(),
+        ] {
+            let (_, matrix) = graph_matrix::graph_map_to_matrix(&graph);
+            for source in 0..matrix.len() {
+                context.search(&matrix, source).unwrap();
+                context.write_tree(matrix.len(), &mut tree);
+                let expected = graph_matrix::shortest_path_tree(&matrix, source).unwrap();
+                assert_eq!(tree, expected);
+
+                for to in 0..matrix.len() {
+                    let expected = build_shortest_path_from_tree(to, &expected).unwrap();
+                    assert_eq!(context.path_to(to, &mut path), Some(expected.distance));
+                    assert_eq!(path, expected.path);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn search_context_csr_test() {
+        let mut context = SearchContext::new();
+        for (graph, expected_shortest_paths) in [
+            graph_samples::sample1(),
+            graph_samples::sample2(),
+            graph_samples::sample3(),
+            graph_samples::sample4(),
+        ] {
+            // converted once, every query runs on ids
+            let graph_csr_named = graph_csr::graph_map_to_csr_named(&graph);
+            let source = graph_csr_named
+                .names
+                .id(&expected_shortest_paths[0].from)
+                .unwrap();
+            context
+                .search_csr(&graph_csr_named.csr, source.index())
+                .unwrap();
+            for expected in expected_shortest_paths {
+                let to = graph_csr_named.names.id(&expected.to).unwrap();
+                let shortest_path = context
+                    .path_to_named(&graph_csr_named.names, to.index())
+                    .unwrap();
+                assert_eq!(shortest_path, expected);
+            }
+        }
+
+        // one-way edge 0 -> 1 and weights at the top of the range
+        let graph_csr = graph_csr::graph_matrix_to_csr(&vec![
+            vec![0, u32::MAX, 0],
+            vec![0, 0, u32::MAX],
+            vec![0, 0, 0],
+        ]);
+        context.search_csr(&graph_csr, 0).unwrap();
+        assert_eq!(context.distance(2), u32::MAX);
+        context.search_csr(&graph_csr, 1).unwrap();
+        assert_eq!(context.distance(0), u32::MAX);
+        assert_eq!(context.distance(2), u32::MAX);
+        assert!(context.search_csr(&graph_csr, 3).is_none());
+    }
+
+    #[test]
+    fn search_context_reset_test() {
+        // node 2 is unreachable from 0
+        let matrix = vec![vec![0, 3, 0], vec![3, 0, 0], vec![0, 0, 0]];
+        let mut context = SearchContext::with_capacity(3);
+
+        // results of the previous search must not leak into the next one
+        context.search(&matrix, 2).unwrap();
+        assert_eq!(context.distance(2), 0);
+        context.search(&matrix, 0).unwrap();
+        assert_eq!(context.distance(2), u32::MAX);
+        assert_eq!(context.previous(2), 2);
+        assert!(context.path_to(2, &mut Vec::new()).is_none());
+
+        // the generation counter wraps around
+        context.generation = u32::MAX;
+        context.search(&matrix, 1).unwrap();
+        assert_eq!(context.generation, 1);
+        assert_eq!(context.distance(0), 3);
+        assert_eq!(context.distance(2), u32::MAX);
+
+        assert!(context.search(&matrix, 3).is_none());
+    }
+}
@@ -0,0 +1,478 @@
+// Graph building and editing with validation
+// Edges are directed, `add_undirected_edge` inserts both directions at once.
+// Every edit is validated before anything changes, so a failed edit leaves the graph untouched.
+
+use crate::graph_type::*;
+use std::collections::HashMap;
+
+/// Editing API shared by `GraphMap` and `GraphVector`
+pub trait GraphEdit {
+    fn contains_node(&self, node: &str) -> bool;
+
+    fn edge_distance(&self, from: &str, to: &str) -> Option<u32>;
+
+    fn add_node(&mut self, node: &str) -> Result<(), GraphError>;
+
+    /// Removes the node together with all edges pointing to it
+    fn remove_node(&mut self, node: &str) -> Result<(), GraphError>;
+
+    /// Inserts a new edge, adding an existing edge again with the same distance is accepted
+    fn add_edge(&mut self, from: &str, to: &str, distance: u32) -> Result<(), GraphError>;
+
+    fn update_edge(&mut self, from: &str, to: &str, distance: u32) -> Result<(), GraphError>;
+
+    fn remove_edge(&mut self, from: &str, to: &str) -> Result<(), GraphError>;
+
+    fn add_undirected_edge(&mut self, a: &str, b: &str, distance: u32) -> Result<(), GraphError> {
+        validate_new_edge(self, a, b, distance)?;
+        validate_new_edge(self, b, a, distance)?;
+        self.add_edge(a, b, distance)?;
+        self.add_edge(b, a, distance)
+    }
+
+    fn update_undirected_edge(
+        &mut self,
+        a: &str,
+        b: &str,
+        distance: u32,
+    ) -> Result<(), GraphError> {
+        validate_existing_edge(self, a, b, distance)?;
+        validate_existing_edge(self, b, a, distance)?;
+        self.update_edge(a, b, distance)?;
+        self.update_edge(b, a, distance)
+    }
+
+    fn remove_undirected_edge(&mut self, a: &str, b: &str) -> Result<(), GraphError> {
+        check_edge_exists(self, a, b)?;
+        check_edge_exists(self, b, a)?;
+        self.remove_edge(a, b)?;
+        self.remove_edge(b, a)
+    }
+}
+
+fn validate_edge_ends<G: GraphEdit + ?Sized>(
+    graph: &G,
+    from: &str,
+    to: &str,
+    distance: u32,
+) -> Result<(), GraphError> {
+    for node in [from, to] {
+        if !graph.contains_node(node) {
+            return Err(GraphError::MissingNode(node.to_string()));
+        }
+    }
+    if from == to {
+        return Err(GraphError::SelfLoop(from.to_string()));
+    }
+    if distance == 0 {
+        return Err(GraphError::ZeroDistance(from.to_string(), to.to_string()));
+    }
+    Ok(())
+}
+
+fn validate_new_edge<G: GraphEdit + ?Sized>(
+    graph: &G,
+    from: &str,
+    to: &str,
+    distance: u32,
+) -> Result<(), GraphError> {
+    validate_edge_ends(graph, from, to, distance)?;
+    match graph.edge_distance(from, to) {
+        Some(existing) if existing != distance => Err(GraphError::ConflictingDistance {
+            from: from.to_string(),
+            to: to.to_string(),
+            existing,
+            new: distance,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Both nodes and the edge between them must exist, the distance is not looked at
+fn check_edge_exists<G: GraphEdit + ?Sized>(
+    graph: &G,
+    from: &str,
+    to: &str,
+) -> Result<(), GraphError> {
+    for node in [from, to] {
+        if !graph.contains_node(node) {
+            return Err(GraphError::MissingNode(node.to_string()));
+        }
+    }
+    if graph.edge_distance(from, to).is_none() {
+        return Err(GraphError::MissingEdge(from.to_string(), to.to_string()));
+    }
+    Ok(())
+}
+
+fn validate_existing_edge<G: GraphEdit + ?Sized>(
+    graph: &G,
+    from: &str,
+    to: &str,
+    distance: u32,
+) -> Result<(), GraphError> {
+    validate_edge_ends(graph, from, to, distance)?;
+    check_edge_exists(graph, from, to)
+}
+
+impl GraphEdit for GraphMap {
+    fn contains_node(&self, node: &str) -> bool {
+        self.contains_key(node)
+    }
+
+    fn edge_distance(&self, from: &str, to: &str) -> Option<u32> {
+        self.get(from)?.get(to).copied()
+    }
+
+    fn add_node(&mut self, node: &str) -> Result<(), GraphError> {
+        if self.contains_key(node) {
+            return Err(GraphError::NodeExists(node.to_string()));
+        }
+        self.insert(node.to_string(), HashMap::new());
+        Ok(())
+    }
+
+    fn remove_node(&mut self, node: &str) -> Result<(), GraphError> {
+        if self.remove(node).is_none() {
+            return Err(GraphError::MissingNode(node.to_string()));
+        }
+        for edges in self.values_mut() {
+            edges.remove(node);
+        }
+        Ok(())
+    }
+
+    fn add_edge(&mut self, from: &str, to: &str, distance: u32) -> Result<(), GraphError> {
+        validate_new_edge(self, from, to, distance)?;
+        self.get_mut(from).unwrap().insert(to.to_string(), distance);
+        Ok(())
+    }
+
+    fn update_edge(&mut self, from: &str, to: &str, distance: u32) -> Result<(), GraphError> {
+        validate_existing_edge(self, from, to, distance)?;
+        self.get_mut(from).unwrap().insert(to.to_string(), distance);
+        Ok(())
+    }
+
+    fn remove_edge(&mut self, from: &str, to: &str) -> Result<(), GraphError> {
+        match self.get_mut(from).and_then(|edges| edges.remove(to)) {
+            Some(_) => Ok(()),
+            None => Err(GraphError::MissingEdge(from.to_string(), to.to_string())),
+        }
+    }
+}
+
+impl GraphEdit for GraphVector {
+    fn contains_node(&self, node: &str) -> bool {
+        self.iter().any(|x| x.0 == node)
+    }
+
+    fn edge_distance(&self, from: &str, to: &str) -> Option<u32> {
+        let (_, edges) = self.iter().find(|x| x.0 == from)?;
+        edges.iter().find(|x| x.0 == to).map(|x| x.1)
+    }
+
+    fn add_node(&mut self, node: &str) -> Result<(), GraphError> {
+        if self.contains_node(node) {
+            return Err(GraphError::NodeExists(node.to_string()));
+        }
+        self.push((node.to_string(), Vec::new()));
+        Ok(())
+    }
+
+    fn remove_node(&mut self, node: &str) -> Result<(), GraphError> {
+        let position = self
+            .iter()
+            .position(|x| x.0 == node)
+            .ok_or_else(|| GraphError::MissingNode(node.to_string()))?;
+        self.remove(position);
+        for (_, edges) in self.iter_mut() {
+            edges.retain(|x| x.0 != node);
+        }
+        Ok(())
+    }
+
+    fn add_edge(&mut self, from: &str, to: &str, distance: u32) -> Result<(), GraphError> {
+        validate_new_edge(self, from, to, distance)?;
+        if self.edge_distance(from, to).is_none() {
+            let (_, edges) = self.iter_mut().find(|x| x.0 == from).unwrap();
+            edges.push((to.to_string(), distance));
+        }
+        Ok(())
+    }
+
+    fn update_edge(&mut self, from: &str, to: &str, distance: u32) -> Result<(), GraphError> {
+        validate_existing_edge(self, from, to, distance)?;
+        let (_, edges) = self.iter_mut().find(|x| x.0 == from).unwrap();
+        edges.iter_mut().find(|x| x.0 == to).unwrap().1 = distance;
+        Ok(())
+    }
+
+    fn remove_edge(&mut self, from: &str, to: &str) -> Result<(), GraphError> {
+        let missing_edge = || GraphError::MissingEdge(from.to_string(), to.to_string());
+        let (_, edges) = self
+            .iter_mut()
+            .find(|x| x.0 == from)
+            .ok_or_else(missing_edge)?;
+        let position = edges
+            .iter()
+            .position(|x| x.0 == to)
+            .ok_or_else(missing_edge)?;
+        edges.remove(position);
+        Ok(())
+    }
+}
+
+/// Collects nodes and edges, the nodes of an edge are declared on the fly.
+/// All validation happens when the graph is built.
+#[derive(Debug, Default)]
+pub struct GraphBuilder {
+    nodes: Vec<String>,
+    edges: Vec<(String, String, u32)>,
+}
+
+impl GraphBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn node(&mut self, node: &str) -> &mut Self {
+        self.nodes.push(node.to_string());
+        self
+    }
+
+    pub fn edge(&mut self, from: &str, to: &str, distance: u32) -> &mut Self {
+        self.edges
+            .push((from.to_string(), to.to_string(), distance));
+        self
+    }
+
+    pub fn undirected_edge(&mut self, a: &str, b: &str, distance: u32) -> &mut Self {
+        self.edge(a, b, distance).edge(b, a, distance)
+    }
+
+    fn build<G: GraphEdit>(&self, mut graph: G) -> Result<G, GraphError> {
+        let edge_nodes = self.edges.iter().flat_map(|x| [&x.0, &x.1]);
+        for node in self.nodes.iter().chain(edge_nodes) {
+            if !graph.contains_node(node) {
+                graph.add_node(node)?;
+            }
+        }
+        for (from, to, distance) in self.edges.iter() {
+            graph.add_edge(from, to, *distance)?;
+        }
+        Ok(graph)
+    }
+
+    pub fn build_map(&self) -> Result<GraphMap, GraphError> {
+        self.build(GraphMap::new())
+    }
+
+    pub fn build_vector(&self) -> Result<GraphVector, GraphError> {
+        self.build(GraphVector::new())
+    }
+}
+
+/// Checks a map based graph built elsewhere: dangling edge targets, self-loops and zero distances
+pub fn validate_graph_map(graph_map: &GraphMap) -> Result<(), GraphError> {
+    for (from, edges) in graph_map {
+        for (to, distance) in edges {
+            validate_edge_ends(graph_map, from, to, *distance)?;
+        }
+    }
+    Ok(())
+}
+
+/// Same as [`validate_graph_map`], every edge must also exist backwards with the same distance
+pub fn validate_undirected_graph_map(graph_map: &GraphMap) -> Result<(), GraphError> {
+    validate_graph_map(graph_map)?;
+    for (from, edges) in graph_map {
+        for (to, &distance) in edges {
+            match graph_map[to].get(from) {
+                None => return Err(GraphError::MissingEdge(to.clone(), from.clone())),
+                // each conflicting pair is reported the same way whatever the iteration order
+                Some(&reverse) if reverse != distance && from < to => {
+                    return Err(GraphError::ConflictingDistance {
+                        from: from.clone(),
+                        to: to.clone(),
+                        existing: distance,
+                        new: reverse,
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks a vector based graph built elsewhere, in addition to the map checks
+/// duplicate nodes and duplicate edges with conflicting distances are rejected
+pub fn validate_graph_vector(graph_vector: &GraphVector) -> Result<(), GraphError> {
+    let mut nodes: HashMap<&str, HashMap<&str, u32>> = HashMap::new();
+    for (node, _) in graph_vector {
+        if nodes.insert(node, HashMap::new()).is_some() {
+            return Err(GraphError::NodeExists(node.to_string()));
+        }
+    }
+
+    for (from, edges) in graph_vector {
+        for (to, distance) in edges {
+            validate_edge_ends(graph_vector, from, to, *distance)?;
+            let known = nodes.get_mut(from.as_str()).unwrap();
+            if let Some(&existing) = known.get(to.as_str()) {
+                if existing != *distance {
+                    return Err(GraphError::ConflictingDistance {
+                        from: from.clone(),
+                        to: to.clone(),
+                        existing,
+                        new: *distance,
+                    });
+                }
+            }
+            known.insert(to, *distance);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::graph_vector::graph_map_to_vector;
+    use crate::graph_samples;
+
+    fn edit_test<G: GraphEdit>(mut graph: G) {
+        graph.add_node("A").unwrap();
+        graph.add_node("B").unwrap();
+        graph.add_node("C").unwrap();
+        assert_eq!(
+            graph.add_node("A"),
+            Err(GraphError::NodeExists("A".to_string()))
+        );
+
+        graph.add_undirected_edge("A", "B", 4).unwrap();
+        assert_eq!(graph.edge_distance("B", "A"), Some(4));
+        // adding the same edge again is accepted, a different distance is not
+        graph.add_edge("A", "B", 4).unwrap();
+        assert!(matches!(
+            graph.add_undirected_edge("A", "B", 5),
+            Err(GraphError::ConflictingDistance {
+                existing: 4,
+                new: 5,
+                ..
+            })
+        ));
+
+        assert_eq!(
+            graph.add_edge("A", "X", 1),
+            Err(GraphError::MissingNode("X".to_string()))
+        );
+        assert_eq!(
+            graph.add_edge("A", "A", 1),
+            Err(GraphError::SelfLoop("A".to_string()))
+        );
+        assert_eq!(
+            graph.add_edge("A", "C", 0),
+            Err(GraphError::ZeroDistance("A".to_string(), "C".to_string()))
+        );
+
+        // a failed undirected edit changes nothing
+        graph.add_edge("C", "B", 2).unwrap();
+        assert!(graph.add_undirected_edge("B", "C", 3).is_err());
+        assert_eq!(graph.edge_distance("B", "C"), None);
+
+        graph.update_undirected_edge("A", "B", 6).unwrap();
+        assert_eq!(graph.edge_distance("A", "B"), Some(6));
+        assert_eq!(graph.edge_distance("B", "A"), Some(6));
+        assert_eq!(
+            graph.update_edge("B", "C", 1),
+            Err(GraphError::MissingEdge("B".to_string(), "C".to_string()))
+        );
+
+        graph.remove_edge("C", "B").unwrap();
+        assert_eq!(graph.edge_distance("C", "B"), None);
+        assert!(graph.remove_edge("C", "B").is_err());
+
+        // an undirected removal needs both directions and leaves the graph as it is otherwise
+        graph.add_edge("C", "A", 7).unwrap();
+        assert_eq!(
+            graph.remove_undirected_edge("C", "A"),
+            Err(GraphError::MissingEdge("A".to_string(), "C".to_string()))
+        );
+        assert_eq!(graph.edge_distance("C", "A"), Some(7));
+        assert_eq!(
+            graph.remove_undirected_edge("C", "X"),
+            Err(GraphError::MissingNode("X".to_string()))
+        );
+        graph.add_edge("A", "C", 7).unwrap();
+        graph.remove_undirected_edge("A", "C").unwrap();
+        assert_eq!(graph.edge_distance("C", "A"), None);
+
+        graph.remove_node("B").unwrap();
+        assert!(!graph.contains_node("B"));
+        assert_eq!(graph.edge_distance("A", "B"), None);
+        assert!(graph.remove_node("B").is_err());
+    }
+
+    #[test]
+    fn graph_map_edit_test() {
+        edit_test(GraphMap::new());
+    }
+
+    #[test]
+    fn graph_vector_edit_test() {
+        edit_test(GraphVector::new());
+    }
+
+    #[test]
+    fn graph_builder_test() {
+        let (expected, _) = graph_samples::sample2();
+
+        let mut builder = GraphBuilder::new();
+        builder
+            .undirected_edge("A", "B", 6)
+            .undirected_edge("A", "D", 1)
+            .undirected_edge("B", "D", 2)
+            .undirected_edge("B", "E", 2)
+            .undirected_edge("B", "C", 5)
+            .undirected_edge("C", "E", 5)
+            .undirected_edge("D", "E", 1);
+        assert_eq!(builder.build_map().unwrap(), expected);
+
+        let graph_vector = builder.build_vector().unwrap();
+        assert_eq!(graph_vector.len(), 5);
+        assert!(validate_graph_vector(&graph_vector).is_ok());
+
+        builder.node("F").edge("A", "B", 7);
+        assert!(matches!(
+            builder.build_map(),
+            Err(GraphError::ConflictingDistance { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_graph_test() {
+        let (mut graph_map, _) = graph_samples::sample1();
+        assert!(validate_graph_map(&graph_map).is_ok());
+        assert!(validate_graph_vector(&graph_map_to_vector(&graph_map).unwrap()).is_ok());
+
+        graph_map.get_mut("A").unwrap().insert("Z".to_string(), 3);
+        assert_eq!(
+            validate_graph_map(&graph_map),
+            Err(GraphError::MissingNode("Z".to_string()))
+        );
+
+        let graph_vector: GraphVector = vec![
+            (
+                "A".to_string(),
+                vec![("B".to_string(), 1), ("B".to_string(), 2)],
+            ),
+            ("B".to_string(), vec![]),
+        ];
+        assert!(matches!(
+            validate_graph_vector(&graph_vector),
+            Err(GraphError::ConflictingDistance { .. })
+        ));
+    }
+}
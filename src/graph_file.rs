@@ -0,0 +1,569 @@
+// Binary graph format, memory-mapped and used without deserialising
+//
+// Layout, all numbers little endian:
+// - header of 48 bytes: magic "SPGRAPH\0", version u32, flags u32, node count u64, edge count u64,
+//   name data length u64, checksum u64 (FNV-1a of the rest of the header and everything after it)
+// - CSR arrays as u32: offsets (node count + 1), targets (edge count), weights (edge count)
+// - name table: name offsets as u32 (node count + 1), then the UTF-8 name data
+// Every array starts at a multiple of 4 bytes, so a mapped file can be read as `&[u32]` in place.
+// The data is checked once when opened, afterwards the algorithms only slice into the mapping.
+// A trusted open skips the checksum, so the weights are never read, but it still walks the offsets,
+// targets and name table to check the structure; the checksum can be verified later on demand.
+
+use crate::dijkstra::graph_csr;
+use crate::dijkstra::utils::*;
+use crate::graph_type::*;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+pub const FORMAT_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 8] = b"SPGRAPH\0";
+const HEADER_SIZE: usize = 48;
+const CHECKSUM_AT: usize = 40;
+// names are stored in ascending order, so they can be found by binary search
+const FLAG_SORTED_NAMES: u32 = 1;
+
+/// How much of the data `parse` checks beyond the header
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Check {
+    /// Checksum and structure
+    All,
+    /// Array bounds and name table, the weights are not read
+    Structure,
+    /// Nothing, the data was checked before
+    Nothing,
+}
+
+/// Borrowed CSR graph with node names, pointing into an encoded graph.
+/// Runs the same algorithms as [`GraphCsr`] through [`CsrGraph`].
+#[derive(Debug, Clone, Copy)]
+pub struct GraphCsrRef<'a> {
+    pub offsets: &'a [u32],
+    pub targets: &'a [u32],
+    pub weights: &'a [u32],
+    name_offsets: &'a [u32],
+    name_data: &'a [u8],
+    sorted_names: bool,
+}
+
+impl<'a> GraphCsrRef<'a> {
+    /// Reads an encoded graph in place, checking header, checksum and structure.
+    /// The bytes must be aligned to 4, which holds for memory-mapped files.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, GraphFileError> {
+        parse(bytes, Check::All)
+    }
+
+    pub fn name(&self, id: NodeId) -> &'a str {
+        let range =
+            self.name_offsets[id.index()] as usize..self.name_offsets[id.index() + 1] as usize;
+        // the whole name data was checked to be UTF-8 with names starting on char boundaries
+        std::str::from_utf8(&self.name_data[range]).unwrap()
+    }
+
+    pub fn id(&self, name: &str) -> Option<NodeId> {
+        let node_count = self.node_count() as u32;
+        if self.sorted_names {
+            let (mut low, mut high) = (0, node_count);
+            while low < high {
+                let middle = low + (high - low) / 2;
+                match self.name(NodeId(middle)).cmp(name) {
+                    std::cmp::Ordering::Less => low = middle + 1,
+                    std::cmp::Ordering::Greater => high = middle,
+                    std::cmp::Ordering::Equal => return Some(NodeId(middle)),
+                }
+            }
+            None
+        } else {
+            (0..node_count)
+                .map(NodeId)
+                .find(|&id| self.name(id) == name)
+        }
+    }
+
+    /// Owned copy of the graph
+    pub fn to_graph_csr_named(&self) -> GraphCsrNamed {
+        let names = (0..self.node_count() as u32)
+            .map(|id| self.name(NodeId(id)).to_string())
+            .collect();
+        GraphCsrNamed {
+            names: NodeNames::from_names(names),
+            csr: GraphCsr {
+                offsets: self.offsets.to_vec(),
+                targets: self.targets.to_vec(),
+                weights: self.weights.to_vec(),
+            },
+        }
+    }
+}
+
+impl CsrGraph for GraphCsrRef<'_> {
+    fn offsets(&self) -> &[u32] {
+        self.offsets
+    }
+
+    fn targets(&self) -> &[u32] {
+        self.targets
+    }
+
+    fn weights(&self) -> &[u32] {
+        self.weights
+    }
+}
+
+/// FNV-1a over the whole file except the checksum field, enough to catch truncated or damaged files
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes[..CHECKSUM_AT]
+        .iter()
+        .chain(&bytes[HEADER_SIZE..])
+        .fold(0xcbf29ce484222325, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+/// Offsets must start at zero, never decrease and end at `end`
+fn check_offsets(offsets: &[u32], end: usize) -> bool {
+    offsets[0] == 0
+        && offsets[offsets.len() - 1] as usize == end
+        && offsets.windows(2).all(|pair| pair[0] <= pair[1])
+}
+
+fn verify_checksum(bytes: &[u8]) -> Result<(), GraphFileError> {
+    if checksum(bytes) != read_u64(bytes, CHECKSUM_AT) {
+        return Err(GraphFileError::ChecksumMismatch);
+    }
+    Ok(())
+}
+
+fn parse(bytes: &[u8], check: Check) -> Result<GraphCsrRef<'_>, GraphFileError> {
+    if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(GraphFileError::NotAGraphFile);
+    }
+    if bytes.len() < HEADER_SIZE {
+        return Err(GraphFileError::Truncated);
+    }
+    let version = read_u32(bytes, 8);
+    if version != FORMAT_VERSION {
+        return Err(GraphFileError::UnsupportedVersion {
+            found: version,
+            supported: FORMAT_VERSION,
+        });
+    }
+
+    let sorted_names = read_u32(bytes, 12) & FLAG_SORTED_NAMES != 0;
+    let too_large = || GraphFileError::Corrupt("sizes out of range");
+    let node_count = usize::try_from(read_u64(bytes, 16)).map_err(|_| too_large())?;
+    let edge_count = usize::try_from(read_u64(bytes, 24)).map_err(|_| too_large())?;
+    let name_data_length = usize::try_from(read_u64(bytes, 32)).map_err(|_| too_large())?;
+
+    let word_count = node_count
+        .checked_add(1)
+        .and_then(|x| x.checked_mul(2))
+        .and_then(|x| x.checked_add(edge_count.checked_mul(2)?))
+        .ok_or_else(too_large)?;
+    let body_length = word_count
+        .checked_mul(4)
+        .and_then(|x| x.checked_add(name_data_length))
+        .ok_or_else(too_large)?;
+    match bytes.len() - HEADER_SIZE {
+        length if length < body_length => return Err(GraphFileError::Truncated),
+        length if length > body_length => return Err(GraphFileError::Corrupt("trailing data")),
+        _ => {}
+    }
+    if check == Check::All {
+        verify_checksum(bytes)?;
+    }
+
+    // SAFETY: any bit pattern is a valid u32, and the alignment is checked right after
+    let (prefix, words, _) =
+        unsafe { bytes[HEADER_SIZE..HEADER_SIZE + word_count * 4].align_to::<u32>() };
+    if !prefix.is_empty() {
+        return Err(GraphFileError::Misaligned);
+    }
+    let (offsets, words) = words.split_at(node_count + 1);
+    let (targets, words) = words.split_at(edge_count);
+    let (weights, name_offsets) = words.split_at(edge_count);
+    let name_data = &bytes[HEADER_SIZE + word_count * 4..];
+
+    if check != Check::Nothing {
+        if !check_offsets(offsets, edge_count) || targets.iter().any(|&x| x as usize >= node_count)
+        {
+            return Err(GraphFileError::Corrupt("invalid edge arrays"));
+        }
+        let names_valid = check_offsets(name_offsets, name_data_length)
+            && std::str::from_utf8(name_data).is_ok_and(|names| {
+                name_offsets
+                    .iter()
+                    .all(|&x| names.is_char_boundary(x as usize))
+            });
+        // `id` relies on the flag for its binary search, byte order is the order of `str`
+        let name =
+            |node: usize| &name_data[name_offsets[node] as usize..name_offsets[node + 1] as usize];
+        let names_valid = names_valid
+            && (!sorted_names || (1..node_count).all(|node| name(node - 1) < name(node)));
+        if !names_valid {
+            return Err(GraphFileError::Corrupt("invalid name table"));
+        }
+    }
+
+    Ok(GraphCsrRef {
+        offsets,
+        targets,
+        weights,
+        name_offsets,
+        name_data,
+        sorted_names,
+    })
+}
+
+/// Encodes the graph in the binary format
+pub fn encode_graph(graph: &GraphCsrNamed) -> Result<Vec<u8>, GraphFileError> {
+    let names = graph.names.names();
+    let csr = &graph.csr;
+    if names.len() != csr.node_count() {
+        return Err(GraphFileError::Corrupt("node names do not match the graph"));
+    }
+
+    let mut name_offsets: Vec<u32> = Vec::with_capacity(names.len() + 1);
+    name_offsets.push(0);
+    let mut name_data_length: usize = 0;
+    for name in names {
+        name_data_length += name.len();
+        let offset = u32::try_from(name_data_length)
+            .map_err(|_| GraphFileError::Corrupt("name table larger than 4 GiB"))?;
+        name_offsets.push(offset);
+    }
+
+    let mut body: Vec<u8> = Vec::new();
+    // a default graph has no offsets at all, the file always holds the leading zero
+    let offsets: &[u32] = if csr.offsets.is_empty() {
+        &[0]
+    } else {
+        &csr.offsets
+    };
+    for array in [offsets, &csr.targets, &csr.weights, &name_offsets] {
+        for value in array.iter() {
+            body.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    for name in names {
+        body.extend_from_slice(name.as_bytes());
+    }
+
+    let sorted = names.windows(2).all(|pair| pair[0] < pair[1]);
+    let mut bytes: Vec<u8> = Vec::with_capacity(HEADER_SIZE + body.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(if sorted { FLAG_SORTED_NAMES } else { 0 }).to_le_bytes());
+    bytes.extend_from_slice(&(names.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&(csr.edge_count() as u64).to_le_bytes());
+    bytes.extend_from_slice(&(name_data_length as u64).to_le_bytes());
+    bytes.extend_from_slice(&0u64.to_le_bytes());
+    bytes.extend_from_slice(&body);
+    let checksum = checksum(&bytes);
+    bytes[CHECKSUM_AT..HEADER_SIZE].copy_from_slice(&checksum.to_le_bytes());
+    Ok(bytes)
+}
+
+pub fn write_graph_file(
+    path: impl AsRef<Path>,
+    graph: &GraphCsrNamed,
+) -> Result<(), GraphFileError> {
+    let bytes = encode_graph(graph)?;
+    let mut file = File::create(path)?;
+    file.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Memory-mapped graph file
+pub struct GraphFile {
+    mmap: Mmap,
+}
+
+impl GraphFile {
+    /// Maps the file and checks it once, the file must not be changed while it is open
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, GraphFileError> {
+        Self::open_checked(path, Check::All)
+    }
+
+    /// Same as [`GraphFile::open`] without the checksum, only the header and the structure are
+    /// checked: the offsets, targets and names are still read, the weights are not.
+    /// Meant for files from a trusted source, [`GraphFile::verify`] runs the checksum later.
+    pub fn open_trusted(path: impl AsRef<Path>) -> Result<Self, GraphFileError> {
+        Self::open_checked(path, Check::Structure)
+    }
+
+    fn open_checked(path: impl AsRef<Path>, check: Check) -> Result<Self, GraphFileError> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is read only, changing the file underneath is documented as not allowed
+        let mmap = unsafe { Mmap::map(&file)? };
+        parse(&mmap, check)?;
+        Ok(GraphFile { mmap })
+    }
+
+    /// Compares the checksum with the data, reading the whole mapping
+    pub fn verify(&self) -> Result<(), GraphFileError> {
+        verify_checksum(&self.mmap)
+    }
+
+    /// The graph inside the mapping, without copying
+    pub fn graph(&self) -> GraphCsrRef<'_> {
+        parse(&self.mmap, Check::Nothing).unwrap()
+    }
+}
+
+/// Shortest path between named nodes, names are only read for the nodes of the path
+pub fn shortest_path_named(graph: &GraphCsrRef, from: &str, to: &str) -> Option<ShortestPathNamed> {
+    let from = graph.id(from)?;
+    let to = graph.id(to)?;
+    let tree = graph_csr::shortest_path_tree(graph, from.index())?;
+    let shortest_path = build_shortest_path_from_tree(to.index(), &tree)?;
+
+    let name = |node: usize| graph.name(NodeId(node as u32)).to_string();
+    Some(ShortestPathNamed {
+        from: name(shortest_path.from),
+        to: name(shortest_path.to),
+        distance: shortest_path.distance,
+        path: shortest_path.path.into_iter().map(name).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crp;
+    use crate::crp::customization::customize;
+    use crate::crp::partition::{partition, Overlay};
+    use crate::dijkstra::workspace::SearchContext;
+    use crate::dijkstra::{delta_stepping, parallel};
+    use crate::graph_samples;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "shortest_path_{}_{}.graph",
+            std::process::id(),
+            name
+        ))
+    }
+
+    fn write_bytes(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = temp_path(name);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn graph_file_roundtrip_test() {
+        let (graph, expected_shortest_paths) = graph_samples::sample1();
+        let graph_csr_named = graph_csr::graph_map_to_csr_named(&graph);
+        let path = temp_path("roundtrip");
+        write_graph_file(&path, &graph_csr_named).unwrap();
+
+        let graph_file = GraphFile::open(&path).unwrap();
+        let graph_ref = graph_file.graph();
+        assert_eq!(graph_ref.node_count(), 9);
+        assert_eq!(graph_ref.edge_count(), graph_csr_named.csr.edge_count());
+        assert_eq!(graph_ref.id("E"), Some(NodeId(4)));
+        assert_eq!(graph_ref.id("X"), None);
+        assert_eq!(graph_ref.to_graph_csr_named(), graph_csr_named);
+
+        for source in 0..graph_ref.node_count() {
+            assert_eq!(
+                graph_csr::shortest_path_tree(&graph_ref, source),
+                graph_csr::shortest_path_tree(&graph_csr_named.csr, source)
+            );
+        }
+        for expected in expected_shortest_paths {
+            let shortest_path = shortest_path_named(&graph_ref, &expected.from, &expected.to);
+            assert_eq!(shortest_path.unwrap(), expected);
+        }
+
+        drop(graph_file);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn graph_file_algorithms_test() {
+        let (graph, _) = graph_samples::sample3();
+        let graph_csr_named = graph_csr::graph_map_to_csr_named(&graph);
+        let csr = &graph_csr_named.csr;
+        let path = temp_path("algorithms");
+        write_graph_file(&path, &graph_csr_named).unwrap();
+        let graph_file = GraphFile::open(&path).unwrap();
+        let graph_ref = graph_file.graph();
+
+        // the mapped graph runs the CSR algorithms without being copied
+        let sources: Vec<usize> = (0..csr.node_count()).collect();
+        let trees = parallel::shortest_path_trees_csr(&graph_ref, &sources, &Default::default());
+        assert_eq!(
+            trees,
+            parallel::shortest_path_trees_csr(csr, &sources, &Default::default())
+        );
+
+        let overlay = Overlay::new(&graph_ref, partition(&graph_ref, &[2, 4]).unwrap()).unwrap();
+        let customization = customize(&graph_ref, &overlay, graph_ref.weights.to_vec()).unwrap();
+        let mut context = SearchContext::new();
+        for (source, tree) in trees.unwrap().iter().enumerate() {
+            let delta_tree =
+                delta_stepping::shortest_path_tree(&graph_ref, source, &Default::default());
+            assert_eq!(delta_tree.as_ref(), Some(tree));
+
+            context.search_csr(&graph_ref, source).unwrap();
+            for node in tree {
+                assert_eq!(context.distance(node.to), node.distance);
+                let shortest_path = crp::query::shortest_path(
+                    &graph_ref,
+                    &overlay,
+                    &customization,
+                    source,
+                    node.to,
+                );
+                assert_eq!(shortest_path.map(|x| x.distance), Some(node.distance));
+            }
+        }
+
+        drop(graph_file);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn graph_file_unsorted_names_test() {
+        let mut names = NodeNames::new();
+        for name in ["ß", "b", "a"] {
+            names.intern(name);
+        }
+        let graph = GraphCsrNamed {
+            names,
+            csr: graph_csr::graph_matrix_to_csr(&vec![vec![0, 2, 0], vec![2, 0, 1], vec![0, 1, 0]]),
+        };
+        let path = write_bytes("unsorted", &encode_graph(&graph).unwrap());
+
+        let graph_file = GraphFile::open(&path).unwrap();
+        let graph_ref = graph_file.graph();
+        assert_eq!(graph_ref.id("a"), Some(NodeId(2)));
+        assert_eq!(graph_ref.name(NodeId(0)), "ß");
+        let shortest_path = shortest_path_named(&graph_ref, "ß", "a").unwrap();
+        assert_eq!(shortest_path.distance, 3);
+        assert_eq!(shortest_path.path, ["ß", "b", "a"]);
+
+        drop(graph_file);
+        std::fs::remove_file(&path).unwrap();
+
+        // a sorted flag on unsorted names would break `id`, even with a matching checksum
+        let mut forged = encode_graph(&graph).unwrap();
+        forged[12..16].copy_from_slice(&FLAG_SORTED_NAMES.to_le_bytes());
+        let checksum = checksum(&forged);
+        forged[CHECKSUM_AT..HEADER_SIZE].copy_from_slice(&checksum.to_le_bytes());
+        let path = write_bytes("forged", &forged);
+        assert!(matches!(
+            GraphFile::open(&path),
+            Err(GraphFileError::Corrupt("invalid name table"))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn graph_file_errors_test() {
+        let (graph, _) = graph_samples::sample2();
+        let bytes = encode_graph(&graph_csr::graph_map_to_csr_named(&graph)).unwrap();
+
+        let open = |name: &str, bytes: &[u8]| {
+            let path = write_bytes(name, bytes);
+            let result = GraphFile::open(&path).map(|_| ());
+            std::fs::remove_file(&path).unwrap();
+            result
+        };
+
+        let mut newer = bytes.clone();
+        newer[8..12].copy_from_slice(&2u32.to_le_bytes());
+        assert!(matches!(
+            open("version", &newer),
+            Err(GraphFileError::UnsupportedVersion {
+                found: 2,
+                supported: 1
+            })
+        ));
+
+        let mut damaged = bytes.clone();
+        let last = damaged.len() - 1;
+        damaged[last] ^= 1;
+        assert!(matches!(
+            open("damaged", &damaged),
+            Err(GraphFileError::ChecksumMismatch)
+        ));
+
+        // the header is covered by the checksum as well
+        let mut flags = bytes.clone();
+        flags[12] ^= FLAG_SORTED_NAMES as u8;
+        assert!(matches!(
+            open("flags", &flags),
+            Err(GraphFileError::ChecksumMismatch)
+        ));
+
+        assert!(matches!(
+            open("truncated", &bytes[..bytes.len() - 4]),
+            Err(GraphFileError::Truncated)
+        ));
+        assert!(matches!(
+            open("magic", b"{\"A\": {}}"),
+            Err(GraphFileError::NotAGraphFile)
+        ));
+        assert!(matches!(
+            GraphFile::open(temp_path("missing")),
+            Err(GraphFileError::Io(_))
+        ));
+        assert!(open("valid", &bytes).is_ok());
+
+        let empty = encode_graph(&GraphCsrNamed::default()).unwrap();
+        assert!(open("empty", &empty).is_ok());
+    }
+
+    #[test]
+    fn graph_file_trusted_test() {
+        let (graph, _) = graph_samples::sample2();
+        let graph_csr_named = graph_csr::graph_map_to_csr_named(&graph);
+        let bytes = encode_graph(&graph_csr_named).unwrap();
+
+        let open_trusted = |name: &str, bytes: &[u8]| {
+            let path = write_bytes(name, bytes);
+            let result = GraphFile::open_trusted(&path).map(|x| x.verify());
+            std::fs::remove_file(&path).unwrap();
+            result
+        };
+        assert!(matches!(open_trusted("trusted", &bytes), Ok(Ok(()))));
+
+        let first_target = HEADER_SIZE + (graph_csr_named.csr.node_count() + 1) * 4;
+        let first_weight = first_target + graph_csr_named.csr.edge_count() * 4;
+
+        // a damaged weight passes the trusted open, the checksum is left to verify
+        let mut damaged = bytes.clone();
+        damaged[first_weight] ^= 1;
+        assert!(matches!(
+            open_trusted("trusted_damaged", &damaged),
+            Ok(Err(GraphFileError::ChecksumMismatch))
+        ));
+
+        // the structure is still checked
+        let mut corrupt = bytes.clone();
+        corrupt[first_target..first_target + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            open_trusted("trusted_corrupt", &corrupt),
+            Err(GraphFileError::Corrupt(_))
+        ));
+        assert!(matches!(
+            open_trusted("trusted_truncated", &bytes[..bytes.len() - 4]),
+            Err(GraphFileError::Truncated)
+        ));
+    }
+}
@@ -1,6 +1,6 @@
+use crate::graph_builder::GraphBuilder;
 use crate::graph_type::*;
 use serde_json::json;
-use std::collections::HashMap;
 
 pub fn sample1() -> (GraphMap, Vec<ShortestPathNamed>) {
     let input_graph_edges = [
@@ -20,10 +20,7 @@ pub fn sample1() -> (GraphMap, Vec<ShortestPathNamed>) {
         ("H", "I", 7),
     ];
 
-    let mut graph = GraphMap::new();
-    input_graph_edges
-        .iter()
-        .for_each(|x| add_edge_from_named_node(&mut graph, x.0, x.1, x.2));
+    let graph = build_undirected_graph(&input_graph_edges);
 
     /*
     Graph
@@ -96,10 +93,7 @@ pub fn sample2() -> (GraphMap, Vec<ShortestPathNamed>) {
         ("D", "E", 1),
     ];
 
-    let mut graph = GraphMap::new();
-    input_graph_edges
-        .iter()
-        .for_each(|x| add_edge_from_named_node(&mut graph, x.0, x.1, x.2));
+    let graph = build_undirected_graph(&input_graph_edges);
 
     /*
     Shortest-path tree:
@@ -126,7 +120,7 @@ pub fn sample2() -> (GraphMap, Vec<ShortestPathNamed>) {
     "#;
 
     let expected_shortest_paths: Vec<ShortestPathNamed> =
-        serde_json::from_str(&input_shortest_paths).unwrap();
+        serde_json::from_str(input_shortest_paths).unwrap();
 
     (graph, expected_shortest_paths)
 }
@@ -144,10 +138,7 @@ pub fn sample3() -> (GraphMap, Vec<ShortestPathNamed>) {
         ("E", "F", 6),
     ];
 
-    let mut graph = GraphMap::new();
-    input_graph_edges
-        .iter()
-        .for_each(|x| add_edge_from_named_node(&mut graph, x.0, x.1, x.2));
+    let graph = build_undirected_graph(&input_graph_edges);
 
     /*
     Shortest-path tree:
@@ -194,10 +185,7 @@ pub fn sample4() -> (GraphMap, Vec<ShortestPathNamed>) {
         ("E", "F", 9),
     ];
 
-    let mut graph = GraphMap::new();
-    input_graph_edges
-        .iter()
-        .for_each(|x| add_edge_from_named_node(&mut graph, x.0, x.1, x.2));
+    let graph = build_undirected_graph(&input_graph_edges);
 
     let input_shortest_paths = json!([
         {"from": "A", "to": "A", "distance": 0,  "path": ["A", "A"]},
@@ -214,22 +202,37 @@ pub fn sample4() -> (GraphMap, Vec<ShortestPathNamed>) {
     (graph, expected_shortest_paths)
 }
 
-fn add_edge_from_named_node(graph: &mut GraphMap, a: &str, b: &str, distance: u32) {
-    let a_node = graph.get_mut(a);
-    if a_node.is_some() {
-        a_node.unwrap().insert(b.to_string(), distance);
-    } else {
-        let mut edges = HashMap::new();
-        edges.insert(b.to_string(), distance);
-        graph.insert(a.to_string(), edges);
+/// Map based graph with both directions of every edge, panics on invalid edges
+pub fn build_undirected_graph(edges: &[(&str, &str, u32)]) -> GraphMap {
+    let mut builder = GraphBuilder::new();
+    for (a, b, distance) in edges {
+        builder.undirected_edge(a, b, *distance);
     }
+    builder.build_map().unwrap()
+}
 
-    let b_node = graph.get_mut(b);
-    if b_node.is_some() {
-        b_node.unwrap().insert(a.to_string(), distance);
-    } else {
-        let mut edges = HashMap::new();
-        edges.insert(a.to_string(), distance);
-        graph.insert(b.to_string(), edges);
+/// Map based graph with one-way edges, panics on invalid edges
+pub fn build_directed_graph(edges: &[(&str, &str, u32)]) -> GraphMap {
+    let mut builder = GraphBuilder::new();
+    for (from, to, distance) in edges {
+        builder.edge(from, to, *distance);
+    }
+    builder.build_map().unwrap()
+}
+
+/// Undirected graph of `node_count` numbered nodes, panics on invalid edges.
+/// Names are zero padded numbers, so the sorted names keep the numbering.
+pub fn build_numbered_undirected_graph(
+    node_count: usize,
+    edges: &[(usize, usize, u32)],
+) -> GraphMap {
+    let name = |node: usize| format!("{:04}", node);
+    let mut builder = GraphBuilder::new();
+    for node in 0..node_count {
+        builder.node(&name(node));
+    }
+    for &(a, b, distance) in edges {
+        builder.undirected_edge(&name(a), &name(b), distance);
     }
+    builder.build_map().unwrap()
 }
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 pub type GraphMap = HashMap<String, HashMap<String, u32>>;
 pub type GraphVector = Vec<(String, Vec<(String, u32)>)>;
 pub type GraphMatrix = Vec<Vec<u32>>;
+pub type GraphEdgeList = Vec<(String, String, u32)>;
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct ShortestPathTreeNode {
@@ -40,3 +41,484 @@ pub struct ShortestPathNamed {
     pub distance: u32,
     pub path: Vec<String>,
 }
+
+/// Shortest-path DAG node: keeps every predecessor lying on some shortest path.
+/// `path_count` saturates at `u64::MAX` instead of overflowing.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShortestPathDagNode {
+    pub from: usize,
+    pub to: usize,
+    pub distance: u32,
+    pub previous: Vec<usize>,
+    pub path_count: u64,
+}
+
+pub type ShortestPathDag = Vec<ShortestPathDagNode>;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShortestPathDagNodeNamed {
+    pub from: String,
+    pub to: String,
+    pub distance: u32,
+    pub previous: Vec<String>,
+    pub path_count: u64,
+}
+
+pub type ShortestPathDagNamed = Vec<ShortestPathDagNodeNamed>;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpanningTreeEdge {
+    pub from: usize,
+    pub to: usize,
+    pub distance: u32,
+}
+
+/// Minimum spanning tree (or forest, for disconnected graphs)
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpanningTree {
+    pub edges: Vec<SpanningTreeEdge>,
+    pub total_distance: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpanningTreeEdgeNamed {
+    pub from: String,
+    pub to: String,
+    pub distance: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpanningTreeNamed {
+    pub edges: Vec<SpanningTreeEdgeNamed>,
+    pub total_distance: u64,
+}
+
+/// One leg of a via route, `start` and `end` are inclusive positions in the combined path
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ViaRouteLeg {
+    pub from: String,
+    pub to: String,
+    pub distance: u32,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ViaRouteNamed {
+    pub from: String,
+    pub to: String,
+    pub distance: u32,
+    pub path: Vec<String>,
+    pub legs: Vec<ViaRouteLeg>,
+}
+
+/// Eccentricity based metrics, node numbers follow the names returned by `graph_map_to_matrix`.
+/// Nodes that cannot reach every other node have an infinite (`u32::MAX`) eccentricity.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct GraphMetrics {
+    pub eccentricity: Vec<u32>,
+    pub diameter: u32,
+    pub radius: u32,
+    pub center: Vec<usize>,
+    pub periphery: Vec<usize>,
+}
+
+/// Reason why a checked shortest path query found no path
+#[derive(Debug, PartialEq)]
+pub enum ShortestPathError {
+    MissingNode(String),
+    DifferentComponents,
+    Unreachable,
+}
+
+impl std::fmt::Display for ShortestPathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShortestPathError::MissingNode(name) => write!(f, "missing node: {}", name),
+            ShortestPathError::DifferentComponents => write!(f, "unreachable: different components"),
+            ShortestPathError::Unreachable => write!(f, "unreachable"),
+        }
+    }
+}
+
+impl std::error::Error for ShortestPathError {}
+
+/// Node reached by an isochrone search, `band` is the smallest budget covering its distance
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct IsochroneNodeNamed {
+    pub to: String,
+    pub distance: u32,
+    pub previous: String,
+    pub band: u32,
+}
+
+/// Approximate Steiner tree, `steiner_points` are the non-terminal nodes it passes through
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SteinerTreeNamed {
+    pub edges: Vec<SpanningTreeEdgeNamed>,
+    pub total_distance: u64,
+    pub steiner_points: Vec<String>,
+}
+
+/// Ordered visit of a set of stops, `path` is the expanded node path of the whole tour
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct TourNamed {
+    pub stops: Vec<String>,
+    pub distance: u32,
+    pub path: Vec<String>,
+}
+
+/// Best detour when the path edge `from`-`to` fails, `increase` is `u32::MAX` if none exists
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplacementPath {
+    pub from: usize,
+    pub to: usize,
+    pub detour: Option<ShortestPath>,
+    pub increase: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplacementPathNamed {
+    pub from: String,
+    pub to: String,
+    pub detour: Option<ShortestPathNamed>,
+    pub increase: u32,
+}
+
+/// Validation error of the graph building and editing API
+#[derive(Debug, PartialEq)]
+pub enum GraphError {
+    NodeExists(String),
+    MissingNode(String),
+    MissingEdge(String, String),
+    SelfLoop(String),
+    ZeroDistance(String, String),
+    ConflictingDistance {
+        from: String,
+        to: String,
+        existing: u32,
+        new: u32,
+    },
+}
+
+impl std::fmt::Display for GraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphError::NodeExists(node) => write!(f, "node already exists: {}", node),
+            GraphError::MissingNode(node) => write!(f, "missing node: {}", node),
+            GraphError::MissingEdge(from, to) => write!(f, "missing edge: {} -> {}", from, to),
+            GraphError::SelfLoop(node) => write!(f, "self-loop on node: {}", node),
+            GraphError::ZeroDistance(from, to) => {
+                write!(f, "zero distance on edge: {} -> {}", from, to)
+            }
+            GraphError::ConflictingDistance {
+                from,
+                to,
+                existing,
+                new,
+            } => write!(
+                f,
+                "conflicting distance on edge {} -> {}: {} and {}",
+                from, to, existing, new
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+/// New distance of the edge `from` -> `to`, zero removes the edge as in `GraphMatrix`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EdgeUpdate {
+    pub from: usize,
+    pub to: usize,
+    pub distance: u32,
+}
+
+/// Compressed sparse row graph with directed edges,
+/// the edges of node `i` are at `offsets[i]..offsets[i + 1]` of `targets` and `weights`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GraphCsr {
+    pub offsets: Vec<u32>,
+    pub targets: Vec<u32>,
+    pub weights: Vec<u32>,
+}
+
+/// Read access to a CSR graph, implemented by [`GraphCsr`] and by graphs read in place from a file
+pub trait CsrGraph {
+    fn offsets(&self) -> &[u32];
+    fn targets(&self) -> &[u32];
+    fn weights(&self) -> &[u32];
+
+    fn node_count(&self) -> usize {
+        self.offsets().len().saturating_sub(1)
+    }
+
+    fn edge_count(&self) -> usize {
+        self.targets().len()
+    }
+
+    /// Outgoing edges of `node` as `(to, distance)`
+    fn edges(&self, node: usize) -> impl Iterator<Item = (usize, u32)> + '_ {
+        let range = self.offsets()[node] as usize..self.offsets()[node + 1] as usize;
+        self.targets()[range.clone()]
+            .iter()
+            .zip(self.weights()[range].iter())
+            .map(|(&to, &distance)| (to as usize, distance))
+    }
+}
+
+impl CsrGraph for GraphCsr {
+    fn offsets(&self) -> &[u32] {
+        &self.offsets
+    }
+
+    fn targets(&self) -> &[u32] {
+        &self.targets
+    }
+
+    fn weights(&self) -> &[u32] {
+        &self.weights
+    }
+}
+
+/// Index of a node in a graph with interned names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct NodeId(pub u32);
+
+impl NodeId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Interner mapping node names to ids and back, each name is stored once per direction.
+/// Ids are given out in insertion order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeNames {
+    names: Vec<String>,
+    ids: HashMap<String, NodeId>,
+}
+
+impl NodeNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ids follow the order of `names`, repeated names keep their first id
+    pub fn from_names(names: Vec<String>) -> Self {
+        let mut node_names = NodeNames {
+            names: Vec::with_capacity(names.len()),
+            ids: HashMap::with_capacity(names.len()),
+        };
+        for name in names {
+            node_names.intern(&name);
+        }
+        node_names
+    }
+
+    /// Ids follow the sorted order of `names`, as the graph conversions number their nodes
+    pub fn sorted<'a>(names: impl Iterator<Item = &'a String>) -> Self {
+        let mut names: Vec<&String> = names.collect();
+        names.sort();
+        names.dedup();
+
+        let mut node_names = NodeNames {
+            names: Vec::with_capacity(names.len()),
+            ids: HashMap::with_capacity(names.len()),
+        };
+        for name in names {
+            node_names.intern(name);
+        }
+        node_names
+    }
+
+    /// Id of `name`, added if it is not known yet
+    pub fn intern(&mut self, name: &str) -> NodeId {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = NodeId(self.names.len() as u32);
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        id
+    }
+
+    pub fn id(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: NodeId) -> &String {
+        &self.names[id.index()]
+    }
+
+    /// All names, indexed by id
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn into_names(self) -> Vec<String> {
+        self.names
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Named form of a numbered path, numbers being id indexes
+    pub fn resolve_path(&self, shortest_path: &ShortestPath) -> ShortestPathNamed {
+        ShortestPathNamed {
+            from: self.names[shortest_path.from].clone(),
+            to: self.names[shortest_path.to].clone(),
+            distance: shortest_path.distance,
+            path: shortest_path
+                .path
+                .iter()
+                .map(|&node| self.names[node].clone())
+                .collect(),
+        }
+    }
+
+    /// Named view of a numbered tree, names are only looked up when asked for
+    pub fn view_tree<'a>(
+        &'a self,
+        shortest_path_tree: &'a ShortestPathTree,
+    ) -> ShortestPathTreeView<'a> {
+        ShortestPathTreeView {
+            names: self,
+            tree: shortest_path_tree,
+        }
+    }
+
+    /// Named form of a numbered tree, numbers being id indexes.
+    /// Every name is cloned, [`NodeNames::view_tree`] avoids that.
+    pub fn resolve_tree(&self, shortest_path_tree: &ShortestPathTree) -> ShortestPathTreeNamed {
+        shortest_path_tree
+            .iter()
+            .map(|node| ShortestPathTreeNodeNamed {
+                from: self.names[node.from].clone(),
+                to: self.names[node.to].clone(),
+                distance: node.distance,
+                previous: self.names[node.previous].clone(),
+            })
+            .collect()
+    }
+}
+
+/// Numbered tree seen through the names of its graph, tree nodes are indexed by id
+#[derive(Debug, Clone, Copy)]
+pub struct ShortestPathTreeView<'a> {
+    names: &'a NodeNames,
+    tree: &'a ShortestPathTree,
+}
+
+impl<'a> ShortestPathTreeView<'a> {
+    pub fn tree(&self) -> &'a ShortestPathTree {
+        self.tree
+    }
+
+    fn node(&self, name: &str) -> Option<&'a ShortestPathTreeNode> {
+        self.tree.get(self.names.id(name)?.index())
+    }
+
+    /// Distance to `to`, `None` if it is unknown or not reachable
+    pub fn distance(&self, to: &str) -> Option<u32> {
+        self.node(to)
+            .map(|node| node.distance)
+            .filter(|&distance| distance != u32::MAX)
+    }
+
+    /// Node before `to` on its shortest path, the source is its own previous node
+    pub fn previous(&self, to: &str) -> Option<&'a str> {
+        let node = self.node(to)?;
+        if node.distance == u32::MAX {
+            return None;
+        }
+        Some(&self.names.names[node.previous])
+    }
+
+    /// Shortest path to `to`, only its own names are cloned
+    pub fn shortest_path(&self, to: &str) -> Option<ShortestPathNamed> {
+        let node = self.node(to)?;
+        if node.distance == u32::MAX {
+            return None;
+        }
+
+        let mut path: Vec<usize> = vec![node.to];
+        let mut previous = node.previous;
+        while previous != node.from {
+            path.push(previous);
+            previous = self.tree[previous].previous;
+        }
+        path.push(node.from);
+        path.reverse();
+
+        Some(self.names.resolve_path(&ShortestPath {
+            from: node.from,
+            to: node.to,
+            distance: node.distance,
+            path,
+        }))
+    }
+
+    /// Named copy of the whole tree
+    pub fn to_named(&self) -> ShortestPathTreeNamed {
+        self.names.resolve_tree(self.tree)
+    }
+}
+
+/// Adjacency matrix owning the names of its nodes, node ids are matrix indexes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphMatrixNamed {
+    pub names: NodeNames,
+    pub matrix: GraphMatrix,
+}
+
+/// CSR graph owning the names of its nodes, node ids are CSR indexes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphCsrNamed {
+    pub names: NodeNames,
+    pub csr: GraphCsr,
+}
+
+/// Error of reading or writing the binary graph format
+#[derive(Debug)]
+pub enum GraphFileError {
+    Io(std::io::Error),
+    NotAGraphFile,
+    UnsupportedVersion { found: u32, supported: u32 },
+    Truncated,
+    ChecksumMismatch,
+    Misaligned,
+    Corrupt(&'static str),
+}
+
+impl std::fmt::Display for GraphFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphFileError::Io(error) => write!(f, "io error: {}", error),
+            GraphFileError::NotAGraphFile => write!(f, "not a graph file"),
+            GraphFileError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "unsupported graph file version {}, supported version is {}",
+                found, supported
+            ),
+            GraphFileError::Truncated => write!(f, "graph file is truncated"),
+            GraphFileError::ChecksumMismatch => write!(f, "graph file checksum mismatch"),
+            GraphFileError::Misaligned => write!(f, "graph data is not aligned to 4 bytes"),
+            GraphFileError::Corrupt(reason) => write!(f, "corrupt graph file: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for GraphFileError {}
+
+impl From<std::io::Error> for GraphFileError {
+    fn from(error: std::io::Error) -> Self {
+        GraphFileError::Io(error)
+    }
+}
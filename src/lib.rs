@@ -1,3 +1,13 @@
 pub mod graph_type;
+pub mod graph_builder;
+// the file arrays are read in place, which needs little endian
+#[cfg(target_endian = "little")]
+pub mod graph_file;
 pub mod graph_samples;
+#[cfg(test)]
+mod random_graphs;
 pub mod dijkstra;
+pub mod mst;
+pub mod analytics;
+pub mod tsp;
+pub mod crp;
@@ -0,0 +1,128 @@
+// Implements Kruskal's Minimum Spanning Tree Algorithm
+// Useful resources:
+// https://en.wikipedia.org/wiki/Kruskal%27s_algorithm
+// https://en.wikipedia.org/wiki/Disjoint-set_data_structure
+
+use crate::graph_type::*;
+use crate::mst::utils::*;
+
+/// Union-find with path halving and union by size
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl DisjointSet {
+    pub fn new(count: usize) -> Self {
+        DisjointSet {
+            parent: (0..count).collect(),
+            size: vec![1; count],
+        }
+    }
+
+    pub fn find(&mut self, mut node: usize) -> usize {
+        while self.parent[node] != node {
+            self.parent[node] = self.parent[self.parent[node]];
+            node = self.parent[node];
+        }
+        node
+    }
+
+    /// Merges the sets of `a` and `b`, returns false if they were already joined
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let mut root_a = self.find(a);
+        let mut root_b = self.find(b);
+        if root_a == root_b {
+            return false;
+        }
+
+        if self.size[root_a] < self.size[root_b] {
+            std::mem::swap(&mut root_a, &mut root_b);
+        }
+        self.parent[root_b] = root_a;
+        self.size[root_a] += self.size[root_b];
+        true
+    }
+}
+
+/// Builds the minimum spanning tree (or forest) from numbered edges of `node_count` nodes
+pub fn minimum_spanning_tree(node_count: usize, edges: &[(usize, usize, u32)]) -> SpanningTree {
+    let mut sorted_edges: Vec<&(usize, usize, u32)> = edges.iter().collect();
+    sorted_edges.sort_by_key(|x| (x.2, x.0, x.1));
+
+    let mut disjoint_set = DisjointSet::new(node_count);
+    let mut tree_edges: Vec<SpanningTreeEdge> = Vec::with_capacity(node_count.saturating_sub(1));
+    let mut total_distance = 0;
+    for &&(from, to, distance) in sorted_edges.iter() {
+        if from != to && disjoint_set.union(from, to) {
+            tree_edges.push(SpanningTreeEdge { from, to, distance });
+            total_distance += distance as u64;
+        }
+    }
+
+    SpanningTree {
+        edges: tree_edges,
+        total_distance,
+    }
+}
+
+/// Builds the minimum spanning tree (or forest) from a named edge list
+pub fn minimum_spanning_tree_named(edge_list: &GraphEdgeList) -> SpanningTreeNamed {
+    let node_names = NodeNames::sorted(edge_list.iter().flat_map(|(from, to, _)| [from, to]));
+
+    let index = |name: &String| node_names.id(name).unwrap().index();
+    let edges: Vec<(usize, usize, u32)> = edge_list
+        .iter()
+        .map(|(from, to, distance)| (index(from), index(to), *distance))
+        .collect();
+
+    let tree = minimum_spanning_tree(node_names.len(), &edges);
+    spanning_tree_named_from_numbered(node_names.names(), &tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::graph_matrix::graph_map_to_matrix;
+    use crate::graph_samples;
+    use crate::mst::prim;
+
+    #[test]
+    fn kruskal_sample1_test() {
+        let (graph_map, _) = graph_samples::sample1();
+        let edge_list = graph_map_to_edge_list(&graph_map);
+        assert_eq!(edge_list.len(), 14);
+
+        let tree = minimum_spanning_tree_named(&edge_list);
+        assert_eq!(tree.edges.len(), 8);
+        assert_eq!(tree.total_distance, 36);
+    }
+
+    #[test]
+    fn kruskal_matches_prim_test() {
+        for sample in [
+            graph_samples::sample1,
+            graph_samples::sample2,
+            graph_samples::sample3,
+            graph_samples::sample4,
+        ] {
+            let (graph_map, _) = sample();
+            let (_, graph_matrix) = graph_map_to_matrix(&graph_map);
+            let prim_tree = prim::minimum_spanning_tree(&graph_matrix);
+            let kruskal_tree = minimum_spanning_tree_named(&graph_map_to_edge_list(&graph_map));
+            assert_eq!(prim_tree.total_distance, kruskal_tree.total_distance);
+            assert_eq!(prim_tree.edges.len(), kruskal_tree.edges.len());
+        }
+    }
+
+    #[test]
+    fn disjoint_set_test() {
+        let mut disjoint_set = DisjointSet::new(4);
+        assert!(disjoint_set.union(0, 1));
+        assert!(disjoint_set.union(2, 3));
+        assert!(!disjoint_set.union(1, 0));
+        assert_ne!(disjoint_set.find(0), disjoint_set.find(2));
+        assert!(disjoint_set.union(1, 3));
+        assert_eq!(disjoint_set.find(0), disjoint_set.find(2));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod prim;
+pub mod kruskal;
+pub mod steiner;
+pub mod utils;
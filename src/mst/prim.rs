@@ -0,0 +1,173 @@
+// Implements Prim's Minimum Spanning Tree Algorithm
+// Useful resources:
+// https://en.wikipedia.org/wiki/Prim%27s_algorithm
+// https://www.geeksforgeeks.org/prims-minimum-spanning-tree-mst-greedy-algo-5/
+
+use crate::graph_type::*;
+use crate::mst::utils::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Builds the minimum spanning tree of a matrix based graph.
+/// A disconnected graph gives a spanning forest, one tree per component.
+pub fn minimum_spanning_tree(graph_matrix: &GraphMatrix) -> SpanningTree {
+    let node_count = graph_matrix.len();
+    let mut in_tree = vec![false; node_count];
+    let mut distance = vec![u32::MAX; node_count];
+    let mut previous = vec![usize::MAX; node_count];
+
+    let mut edges: Vec<SpanningTreeEdge> = Vec::with_capacity(node_count.saturating_sub(1));
+    let mut total_distance = 0;
+
+    for root in 0..node_count {
+        if in_tree[root] {
+            continue;
+        }
+
+        // growing a new tree from the first node not covered yet
+        distance[root] = 0;
+        loop {
+            // checking for the closest node outside the tree
+            let mut node = usize::MAX;
+            let mut min_distance = u32::MAX;
+            for index in 0..node_count {
+                if !in_tree[index] && distance[index] < min_distance {
+                    min_distance = distance[index];
+                    node = index;
+                }
+            }
+
+            if node == usize::MAX {
+                break;
+            }
+
+            in_tree[node] = true;
+            if previous[node] != usize::MAX {
+                edges.push(SpanningTreeEdge {
+                    from: previous[node],
+                    to: node,
+                    distance: min_distance,
+                });
+                total_distance += min_distance as u64;
+            }
+
+            let column = &graph_matrix[node];
+            for index in 0..node_count {
+                let edge_distance = column[index];
+                if !in_tree[index] && edge_distance > 0 && edge_distance < distance[index] {
+                    distance[index] = edge_distance;
+                    previous[index] = node;
+                }
+            }
+        }
+    }
+
+    SpanningTree {
+        edges,
+        total_distance,
+    }
+}
+
+/// Builds the minimum spanning tree of an adjacency list based graph using a binary heap.
+/// Edges are treated as undirected, as in `graph_vector_to_matrix`.
+pub fn minimum_spanning_tree_vector(graph_vector: &GraphVector) -> SpanningTreeNamed {
+    let node_names = NodeNames::sorted(graph_vector.iter().map(|x| &x.0));
+
+    let node_count = node_names.len();
+    let mut adjacency: Vec<Vec<(usize, u32)>> = vec![Vec::new(); node_count];
+    for (node1, node_edges) in graph_vector {
+        let index1 = node_names.id(node1).unwrap().index();
+        for (node2, distance) in node_edges {
+            let index2 = node_names.id(node2).unwrap().index();
+            adjacency[index1].push((index2, *distance));
+            adjacency[index2].push((index1, *distance));
+        }
+    }
+
+    let mut in_tree = vec![false; node_count];
+    let mut edges: Vec<SpanningTreeEdge> = Vec::with_capacity(node_count.saturating_sub(1));
+    let mut total_distance = 0;
+    let mut heap: BinaryHeap<Reverse<(u32, usize, usize)>> = BinaryHeap::new();
+
+    for root in 0..node_count {
+        if in_tree[root] {
+            continue;
+        }
+
+        heap.push(Reverse((0, root, root)));
+        while let Some(Reverse((distance, node, previous))) = heap.pop() {
+            if in_tree[node] {
+                continue;
+            }
+
+            in_tree[node] = true;
+            if node != previous {
+                edges.push(SpanningTreeEdge {
+                    from: previous,
+                    to: node,
+                    distance,
+                });
+                total_distance += distance as u64;
+            }
+
+            for &(next, edge_distance) in adjacency[node].iter() {
+                if !in_tree[next] && edge_distance > 0 {
+                    heap.push(Reverse((edge_distance, next, node)));
+                }
+            }
+        }
+    }
+
+    spanning_tree_named_from_numbered(
+        node_names.names(),
+        &SpanningTree {
+            edges,
+            total_distance,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::graph_matrix::graph_map_to_matrix;
+    use crate::dijkstra::graph_vector::graph_map_to_vector;
+    use crate::graph_samples;
+
+    #[test]
+    fn prim_matrix_test() {
+        let (graph_map, _) = graph_samples::sample1();
+        let (names, graph_matrix) = graph_map_to_matrix(&graph_map);
+
+        let tree = minimum_spanning_tree(&graph_matrix);
+        assert_eq!(tree.edges.len(), names.len() - 1);
+        assert_eq!(tree.total_distance, 36);
+        assert_eq!(
+            tree.total_distance,
+            tree.edges.iter().map(|x| x.distance as u64).sum::<u64>()
+        );
+    }
+
+    #[test]
+    fn prim_vector_test() {
+        let (graph_map, _) = graph_samples::sample4();
+        let graph_vector = graph_map_to_vector(&graph_map).unwrap();
+
+        let tree = minimum_spanning_tree_vector(&graph_vector);
+        assert_eq!(tree.edges.len(), 5);
+        assert_eq!(tree.total_distance, 33);
+    }
+
+    #[test]
+    fn prim_forest_test() {
+        let graph_matrix = vec![
+            vec![0, 1, 0, 0],
+            vec![1, 0, 0, 0],
+            vec![0, 0, 0, 5],
+            vec![0, 0, 5, 0],
+        ];
+        let tree = minimum_spanning_tree(&graph_matrix);
+        assert_eq!(tree.edges.len(), 2);
+        assert_eq!(tree.total_distance, 6);
+    }
+}
@@ -0,0 +1,162 @@
+// Approximate Steiner tree (Kou, Markowsky and Berman)
+// Useful resources:
+// https://en.wikipedia.org/wiki/Steiner_tree_problem
+//
+// 1. metric closure of the terminals, one Dijkstra tree per terminal
+// 2. minimum spanning tree of the closure
+// 3. closure edges expanded back into real shortest paths
+// 4. spanning tree of the expanded edges, non-terminal leaves pruned
+// The result is at most twice as heavy as the optimal Steiner tree.
+
+use crate::dijkstra::graph_matrix;
+use crate::dijkstra::utils::*;
+use crate::graph_type::*;
+use crate::mst::{kruskal, prim};
+use std::collections::HashSet;
+
+/// Connects all `terminals` with an approximate Steiner tree.
+/// Returns `None` for unknown terminals or terminals in different components.
+pub fn steiner_tree(graph_map: &GraphMap, terminals: &[&str]) -> Option<SteinerTreeNamed> {
+    let graph = graph_matrix::graph_map_to_matrix_named(graph_map);
+    let (names, matrix) = (graph.names.names(), &graph.matrix);
+
+    let mut terminal_indexes: Vec<usize> = Vec::with_capacity(terminals.len());
+    for terminal in terminals {
+        terminal_indexes.push(graph.names.id(terminal)?.index());
+    }
+    terminal_indexes.sort();
+    terminal_indexes.dedup();
+
+    // metric closure over the terminals
+    let terminal_count = terminal_indexes.len();
+    let mut trees: Vec<ShortestPathTree> = Vec::with_capacity(terminal_count);
+    let mut closure: GraphMatrix = vec![vec![0; terminal_count]; terminal_count];
+    for (i, &terminal) in terminal_indexes.iter().enumerate() {
+        let tree = graph_matrix::shortest_path_tree(matrix, terminal)?;
+        for (j, &other) in terminal_indexes.iter().enumerate() {
+            if tree[other].distance == u32::MAX {
+                return None;
+            }
+            if i != j {
+                closure[i][j] = tree[other].distance;
+            }
+        }
+        trees.push(tree);
+    }
+
+    // expanding the closure spanning tree into graph edges
+    let closure_tree = prim::minimum_spanning_tree(&closure);
+    let mut expanded_edges: HashSet<(usize, usize)> = HashSet::new();
+    for edge in closure_tree.edges {
+        let shortest_path =
+            build_shortest_path_from_tree(terminal_indexes[edge.to], &trees[edge.from])?;
+        for pair in shortest_path.path.windows(2) {
+            expanded_edges.insert((pair[0].min(pair[1]), pair[0].max(pair[1])));
+        }
+    }
+
+    let edges: Vec<(usize, usize, u32)> = expanded_edges
+        .into_iter()
+        .map(|(a, b)| (a, b, matrix[a][b]))
+        .collect();
+    let mut tree_edges = kruskal::minimum_spanning_tree(names.len(), &edges).edges;
+
+    // pruning leaves which are not terminals, until none is left
+    let is_terminal: HashSet<usize> = terminal_indexes.iter().copied().collect();
+    loop {
+        let mut degree = vec![0; names.len()];
+        for edge in tree_edges.iter() {
+            degree[edge.from] += 1;
+            degree[edge.to] += 1;
+        }
+
+        let count = tree_edges.len();
+        tree_edges.retain(|edge| {
+            let prunable = |node: usize| degree[node] == 1 && !is_terminal.contains(&node);
+            !prunable(edge.from) && !prunable(edge.to)
+        });
+        if tree_edges.len() == count {
+            break;
+        }
+    }
+
+    let mut steiner_points: Vec<String> = tree_edges
+        .iter()
+        .flat_map(|edge| [edge.from, edge.to])
+        .filter(|node| !is_terminal.contains(node))
+        .map(|node| names[node].clone())
+        .collect();
+    steiner_points.sort();
+    steiner_points.dedup();
+
+    let total_distance = tree_edges.iter().map(|edge| edge.distance as u64).sum();
+    let edges = tree_edges
+        .into_iter()
+        .map(|edge| SpanningTreeEdgeNamed {
+            from: names[edge.from].clone(),
+            to: names[edge.to].clone(),
+            distance: edge.distance,
+        })
+        .collect();
+
+    Some(SteinerTreeNamed {
+        edges,
+        total_distance,
+        steiner_points,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytics::components::connected_components;
+    use crate::graph_samples;
+    use crate::mst::utils::*;
+
+    #[test]
+    fn steiner_tree_sample1_test() {
+        let (graph_map, _) = graph_samples::sample1();
+
+        let tree = steiner_tree(&graph_map, &["A", "C", "E"]).unwrap();
+        assert_eq!(
+            tree.total_distance,
+            tree.edges.iter().map(|x| x.distance as u64).sum::<u64>()
+        );
+
+        // A-B-C plus C-F-E, or A-H-G-F plus F-C and F-E
+        assert!(tree.total_distance <= 25);
+        assert_eq!(tree.edges.len(), tree.steiner_points.len() + 2);
+
+        // the result is a single tree spanning the terminals
+        let tree_graph = spanning_tree_to_graph_map(&SpanningTreeNamed {
+            edges: tree.edges,
+            total_distance: tree.total_distance,
+        });
+        let components = connected_components(&tree_graph);
+        assert!(components.values().all(|&x| x == 0));
+        for terminal in ["A", "C", "E"] {
+            assert!(components.contains_key(terminal));
+        }
+    }
+
+    #[test]
+    fn steiner_tree_terminals_test() {
+        let (graph_map, _) = graph_samples::sample2();
+
+        // all nodes as terminals gives a spanning tree without Steiner points
+        let tree = steiner_tree(&graph_map, &["A", "B", "C", "D", "E"]).unwrap();
+        assert!(tree.steiner_points.is_empty());
+        assert_eq!(tree.edges.len(), 4);
+
+        // two terminals are joined by their shortest path
+        let tree = steiner_tree(&graph_map, &["A", "C"]).unwrap();
+        assert_eq!(tree.total_distance, 7);
+        assert_eq!(tree.steiner_points, ["D", "E"]);
+
+        let tree = steiner_tree(&graph_map, &["B"]).unwrap();
+        assert!(tree.edges.is_empty());
+        assert_eq!(tree.total_distance, 0);
+
+        assert!(steiner_tree(&graph_map, &["A", "X"]).is_none());
+    }
+}
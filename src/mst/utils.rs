@@ -0,0 +1,75 @@
+use crate::graph_type::*;
+
+/// Converts map based graph into an undirected edge list, each edge listed once
+pub fn graph_map_to_edge_list(graph_map: &GraphMap) -> GraphEdgeList {
+    let mut edge_list: GraphEdgeList = Vec::new();
+    for (node1, map) in graph_map {
+        for (node2, distance) in map {
+            // the reverse direction is skipped unless it is missing from the map
+            let has_reverse = graph_map
+                .get(node2)
+                .is_some_and(|edges| edges.contains_key(node1));
+            if node1 < node2 || !has_reverse {
+                edge_list.push((node1.clone(), node2.clone(), *distance));
+            }
+        }
+    }
+    edge_list.sort();
+    edge_list
+}
+
+pub fn spanning_tree_named_from_numbered(
+    node_names: &[String],
+    spanning_tree: &SpanningTree,
+) -> SpanningTreeNamed {
+    SpanningTreeNamed {
+        edges: spanning_tree
+            .edges
+            .iter()
+            .map(|edge| SpanningTreeEdgeNamed {
+                from: node_names[edge.from].clone(),
+                to: node_names[edge.to].clone(),
+                distance: edge.distance,
+            })
+            .collect(),
+        total_distance: spanning_tree.total_distance,
+    }
+}
+
+/// Exports a spanning tree back into a map based graph, edges are inserted in both directions
+pub fn spanning_tree_to_graph_map(spanning_tree: &SpanningTreeNamed) -> GraphMap {
+    let mut graph_map = GraphMap::new();
+    for edge in spanning_tree.edges.iter() {
+        graph_map
+            .entry(edge.from.clone())
+            .or_default()
+            .insert(edge.to.clone(), edge.distance);
+        graph_map
+            .entry(edge.to.clone())
+            .or_default()
+            .insert(edge.from.clone(), edge.distance);
+    }
+    graph_map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::graph_map;
+    use crate::graph_samples;
+    use crate::mst::kruskal;
+
+    #[test]
+    fn spanning_tree_to_graph_map_test() {
+        let (graph, _) = graph_samples::sample1();
+        let tree = kruskal::minimum_spanning_tree_named(&graph_map_to_edge_list(&graph));
+
+        let tree_graph = spanning_tree_to_graph_map(&tree);
+        assert_eq!(tree_graph.len(), graph.len());
+        assert_eq!(graph_map_to_edge_list(&tree_graph).len(), graph.len() - 1);
+
+        // the exported tree can be searched like any other graph
+        let shortest_path_tree = graph_map::shortest_path_tree(&tree_graph, "A").unwrap();
+        assert_eq!(shortest_path_tree.len(), graph.len());
+    }
+}
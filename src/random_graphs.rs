@@ -0,0 +1,91 @@
+// Random test graphs from a small deterministic generator, good enough for comparing algorithms
+// with a reference implementation. The same seed always gives the same graph.
+
+use crate::dijkstra::graph_csr;
+use crate::graph_type::*;
+use std::collections::HashMap;
+
+pub fn next_random(seed: &mut u64) -> u64 {
+    *seed = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    *seed >> 33
+}
+
+/// Directed graph, each edge exists with `edge_percent` chance and weighs 1 to `max_weight`
+pub fn random_graph_matrix(
+    node_count: usize,
+    edge_percent: u64,
+    max_weight: u64,
+    seed: &mut u64,
+) -> GraphMatrix {
+    let mut matrix = vec![vec![0; node_count]; node_count];
+    for (a, row) in matrix.iter_mut().enumerate() {
+        for (b, weight) in row.iter_mut().enumerate() {
+            if a != b && next_random(seed) % 100 < edge_percent {
+                *weight = (next_random(seed) % max_weight + 1) as u32;
+            }
+        }
+    }
+    matrix
+}
+
+/// Undirected graph, each edge exists with `edge_percent` chance and weighs 1 to `max_weight`
+pub fn random_undirected_graph_matrix(
+    node_count: usize,
+    edge_percent: u64,
+    max_weight: u64,
+    seed: &mut u64,
+) -> GraphMatrix {
+    let mut edges: Vec<(usize, usize, u32)> = Vec::new();
+    for a in 0..node_count {
+        for b in a + 1..node_count {
+            if next_random(seed) % 100 < edge_percent {
+                edges.push((a, b, (next_random(seed) % max_weight + 1) as u32));
+            }
+        }
+    }
+
+    let mut matrix = vec![vec![0; node_count]; node_count];
+    for (a, b, weight) in edges {
+        matrix[a][b] = weight;
+        matrix[b][a] = weight;
+    }
+    matrix
+}
+
+/// Directed CSR graph, see [`random_graph_matrix`]
+pub fn random_graph_csr(
+    node_count: usize,
+    edge_percent: u64,
+    max_weight: u64,
+    seed: &mut u64,
+) -> GraphCsr {
+    let matrix = random_graph_matrix(node_count, edge_percent, max_weight, seed);
+    graph_csr::graph_matrix_to_csr(&matrix)
+}
+
+/// Directed map based graph, see [`random_graph_matrix`].
+/// Nodes are named `N000`, `N001`, ..., so the sorted names keep the matrix indexes.
+pub fn random_graph_map(
+    node_count: usize,
+    edge_percent: u64,
+    max_weight: u64,
+    seed: &mut u64,
+) -> GraphMap {
+    let matrix = random_graph_matrix(node_count, edge_percent, max_weight, seed);
+    let name = |index: usize| format!("N{:03}", index);
+    matrix
+        .iter()
+        .enumerate()
+        .map(|(from, row)| {
+            let edges: HashMap<String, u32> = row
+                .iter()
+                .enumerate()
+                .filter(|x| *x.1 > 0)
+                .map(|(to, &weight)| (name(to), weight))
+                .collect();
+            (name(from), edges)
+        })
+        .collect()
+}
@@ -0,0 +1,443 @@
+// Travelling salesman stop ordering over shortest-path distances
+// Useful resources:
+// https://en.wikipedia.org/wiki/Travelling_salesman_problem
+// https://en.wikipedia.org/wiki/2-opt
+//
+// 1. stop-to-stop distance table, one Dijkstra tree per stop
+// 2. nearest neighbour construction
+// 3. 2-opt and Or-opt improvement until no move shortens the tour
+// 4. the ordered stops expanded into the full node path
+
+use crate::dijkstra::graph_matrix;
+use crate::dijkstra::utils::*;
+use crate::graph_type::*;
+
+#[derive(Debug, Default)]
+pub struct TourOptions {
+    /// Return to the first stop at the end of the tour
+    pub closed: bool,
+    /// Stop to begin with
+    pub start: Option<String>,
+    /// Stop to finish at, ignored for closed tours
+    pub end: Option<String>,
+}
+
+/// Distance table between the given stops, `u32::MAX` marks unreachable pairs.
+/// The trees are returned as well, to expand the tour into node paths later.
+pub fn stop_distance_table(
+    graph_matrix: &GraphMatrix,
+    stops: &[usize],
+) -> Option<(GraphMatrix, Vec<ShortestPathTree>)> {
+    let mut table: GraphMatrix = Vec::with_capacity(stops.len());
+    let mut trees: Vec<ShortestPathTree> = Vec::with_capacity(stops.len());
+    for &stop in stops {
+        let tree = graph_matrix::shortest_path_tree(graph_matrix, stop)?;
+        table.push(stops.iter().map(|&other| tree[other].distance).collect());
+        trees.push(tree);
+    }
+    Some((table, trees))
+}
+
+fn tour_cost(table: &GraphMatrix, order: &[usize], closed: bool) -> u64 {
+    let mut cost: u64 = order
+        .windows(2)
+        .map(|pair| table[pair[0]][pair[1]] as u64)
+        .sum();
+    if closed && order.len() > 1 {
+        cost += table[order[order.len() - 1]][order[0]] as u64;
+    }
+    cost
+}
+
+fn nearest_neighbour(table: &GraphMatrix, first: usize, last: Option<usize>) -> Vec<usize> {
+    let count = table.len();
+    let mut visited = vec![false; count];
+    let mut order: Vec<usize> = Vec::with_capacity(count);
+
+    visited[first] = true;
+    order.push(first);
+    if let Some(last) = last {
+        visited[last] = true;
+    }
+
+    let mut current = first;
+    while let Some(next) = (0..count)
+        .filter(|&x| !visited[x])
+        .min_by_key(|&x| table[current][x])
+    {
+        visited[next] = true;
+        order.push(next);
+        current = next;
+    }
+
+    if let Some(last) = last {
+        if last != first {
+            order.push(last);
+        }
+    }
+    order
+}
+
+/// Cost of the edge between two tour positions, missing ends of an open tour cost nothing
+fn link_cost(table: &GraphMatrix, order: &[usize], from: Option<usize>, to: Option<usize>) -> i64 {
+    match (from, to) {
+        (Some(from), Some(to)) => table[order[from]][order[to]] as i64,
+        _ => 0,
+    }
+}
+
+/// Prefix sums of the tour edges walked forwards and backwards, for asymmetric tables
+fn prefix_costs(table: &GraphMatrix, order: &[usize]) -> (Vec<i64>, Vec<i64>) {
+    let mut forward: Vec<i64> = vec![0; order.len()];
+    let mut backward: Vec<i64> = vec![0; order.len()];
+    for k in 1..order.len() {
+        forward[k] = forward[k - 1] + table[order[k - 1]][order[k]] as i64;
+        backward[k] = backward[k - 1] + table[order[k]][order[k - 1]] as i64;
+    }
+    (forward, backward)
+}
+
+/// Applies 2-opt and Or-opt moves within `order[low..=high]` while the tour gets shorter.
+/// Moves are evaluated by the cost of the edges they change only.
+fn improve(table: &GraphMatrix, order: &mut Vec<usize>, low: usize, high: usize, closed: bool) {
+    let count = order.len();
+    // position after `position`, the last stop of a closed tour leads back to the first one
+    let next_of = |position: usize, len: usize| -> Option<usize> {
+        if position + 1 < len {
+            Some(position + 1)
+        } else if closed {
+            Some(0)
+        } else {
+            None
+        }
+    };
+    let previous_of = |position: usize, len: usize| -> Option<usize> {
+        if position > 0 {
+            Some(position - 1)
+        } else if closed {
+            Some(len - 1)
+        } else {
+            None
+        }
+    };
+
+    let mut improved = true;
+    while improved {
+        improved = false;
+
+        // 2-opt: reversing a segment, the edges inside it change direction
+        let (mut forward, mut backward) = prefix_costs(table, order);
+        for i in low..high {
+            for j in i + 1..=high {
+                let (before, after) = (previous_of(i, count), next_of(j, count));
+                let delta = link_cost(table, order, before, Some(j))
+                    + link_cost(table, order, Some(i), after)
+                    + (backward[j] - backward[i])
+                    - link_cost(table, order, before, Some(i))
+                    - link_cost(table, order, Some(j), after)
+                    - (forward[j] - forward[i]);
+                if delta < 0 {
+                    order[i..=j].reverse();
+                    (forward, backward) = prefix_costs(table, order);
+                    improved = true;
+                }
+            }
+        }
+
+        // Or-opt: moving a segment of up to three stops elsewhere
+        for length in 1..=3 {
+            if high + 1 < low + length {
+                break;
+            }
+            for i in low..=high + 1 - length {
+                let last = i + length - 1;
+                let (before, after) = (previous_of(i, count), next_of(last, count));
+                let removal = link_cost(table, order, before, after)
+                    - link_cost(table, order, before, Some(i))
+                    - link_cost(table, order, Some(last), after);
+
+                // positions of the tour without the segment, mapped back onto `order`
+                let rest_len = count - length;
+                let in_order = |position: usize| {
+                    if position < i {
+                        position
+                    } else {
+                        position + length
+                    }
+                };
+                for position in low..=high + 1 - length {
+                    if position == i {
+                        continue;
+                    }
+                    let a = previous_of(position, rest_len).map(in_order);
+                    let b = if position < rest_len {
+                        Some(in_order(position))
+                    } else {
+                        next_of(rest_len - 1, rest_len).map(in_order)
+                    };
+                    let insertion = link_cost(table, order, a, Some(i))
+                        + link_cost(table, order, Some(last), b)
+                        - link_cost(table, order, a, b);
+                    if removal + insertion < 0 {
+                        let segment: Vec<usize> = order.drain(i..i + length).collect();
+                        order.splice(position..position, segment);
+                        improved = true;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Orders the stops of a distance table, returns the stop positions in visiting order
+pub fn order_stops(
+    table: &GraphMatrix,
+    start: Option<usize>,
+    end: Option<usize>,
+    closed: bool,
+) -> Vec<usize> {
+    let count = table.len();
+    if count == 0 {
+        return Vec::new();
+    }
+    let end = if closed { None } else { end };
+
+    // without a fixed start every stop is tried as the first one
+    let firsts: Vec<usize> = match start {
+        Some(start) => vec![start],
+        None => (0..count).filter(|&x| Some(x) != end).collect(),
+    };
+    let mut order = firsts
+        .into_iter()
+        .map(|first| nearest_neighbour(table, first, end))
+        .min_by_key(|order| tour_cost(table, order, closed))
+        .unwrap_or_else(|| vec![end.unwrap()]);
+
+    // keeping the fixed stops in place, a closed tour can always start with the first stop
+    let low = if start.is_some() || closed { 1 } else { 0 };
+    let high = if end.is_some() {
+        count.saturating_sub(2)
+    } else {
+        count - 1
+    };
+    if low < high {
+        improve(table, &mut order, low, high, closed);
+    }
+    order
+}
+
+/// Chooses the visiting order of `stops` and expands it into a node path.
+/// Returns `None` for unknown stops or if some stops can not reach each other.
+pub fn shortest_tour(
+    graph_map: &GraphMap,
+    stops: &[&str],
+    options: &TourOptions,
+) -> Option<TourNamed> {
+    let graph = graph_matrix::graph_map_to_matrix_named(graph_map);
+    let names = graph.names.names();
+
+    let mut stop_indexes: Vec<usize> = Vec::with_capacity(stops.len() + 2);
+    for stop in stops
+        .iter()
+        .copied()
+        .chain(options.start.as_deref())
+        .chain(options.end.as_deref())
+    {
+        let index = graph.names.id(stop)?.index();
+        if !stop_indexes.contains(&index) {
+            stop_indexes.push(index);
+        }
+    }
+    if stop_indexes.is_empty() {
+        return None;
+    }
+
+    let (table, trees) = stop_distance_table(&graph.matrix, &stop_indexes)?;
+    if table.iter().flatten().any(|&x| x == u32::MAX) {
+        return None;
+    }
+
+    let position_of = |name: &Option<String>| {
+        name.as_ref().map(|x| {
+            stop_indexes
+                .iter()
+                .position(|&i| i == graph.names.id(x).unwrap().index())
+                .unwrap()
+        })
+    };
+    // starting and finishing at the same stop is a closed tour
+    let closed = options.closed || (options.start.is_some() && options.start == options.end);
+    let mut order = order_stops(
+        &table,
+        position_of(&options.start),
+        position_of(&options.end),
+        closed,
+    );
+    if closed && order.len() > 1 {
+        order.push(order[0]);
+    }
+
+    let mut path: Vec<String> = vec![names[stop_indexes[order[0]]].clone()];
+    let mut distance: u32 = 0;
+    for pair in order.windows(2) {
+        let leg = build_shortest_path_from_tree(stop_indexes[pair[1]], &trees[pair[0]])?;
+        distance = distance.checked_add(leg.distance)?;
+        path.extend(leg.path.into_iter().skip(1).map(|x| names[x].clone()));
+    }
+
+    if closed && order.len() > 1 {
+        order.pop();
+    }
+
+    Some(TourNamed {
+        stops: order
+            .into_iter()
+            .map(|x| names[stop_indexes[x]].clone())
+            .collect(),
+        distance,
+        path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_samples;
+
+    fn brute_force(table: &GraphMatrix, order: &mut Vec<usize>, closed: bool, best: &mut u64) {
+        if order.len() == table.len() {
+            *best = (*best).min(tour_cost(table, order, closed));
+            return;
+        }
+        for next in 0..table.len() {
+            if !order.contains(&next) {
+                order.push(next);
+                brute_force(table, order, closed, best);
+                order.pop();
+            }
+        }
+    }
+
+    fn optimal_cost(graph_map: &GraphMap, stops: &[&str], closed: bool) -> u64 {
+        let graph = graph_matrix::graph_map_to_matrix_named(graph_map);
+        let stop_indexes: Vec<usize> = stops
+            .iter()
+            .map(|x| graph.names.id(x).unwrap().index())
+            .collect();
+        let (table, _) = stop_distance_table(&graph.matrix, &stop_indexes).unwrap();
+
+        let mut best = u64::MAX;
+        brute_force(&table, &mut Vec::new(), closed, &mut best);
+        best
+    }
+
+    #[test]
+    fn closed_tour_test() {
+        let (graph_map, _) = graph_samples::sample1();
+        let stops = ["A", "D", "E", "I", "H", "B"];
+
+        let options = TourOptions {
+            closed: true,
+            start: Some("A".to_string()),
+            ..Default::default()
+        };
+        let tour = shortest_tour(&graph_map, &stops, &options).unwrap();
+        assert_eq!(tour.stops.len(), stops.len());
+        assert_eq!(tour.stops[0], "A");
+        assert_eq!(tour.path.first().unwrap(), "A");
+        assert_eq!(tour.path.last().unwrap(), "A");
+        assert_eq!(tour.distance as u64, optimal_cost(&graph_map, &stops, true));
+
+        // consecutive path nodes are connected by an edge
+        for pair in tour.path.windows(2) {
+            assert!(graph_map[&pair[0]].contains_key(&pair[1]));
+        }
+    }
+
+    #[test]
+    fn open_tour_test() {
+        let (graph_map, _) = graph_samples::sample1();
+        let stops = ["B", "D", "E", "G", "I"];
+
+        let tour = shortest_tour(&graph_map, &stops, &TourOptions::default()).unwrap();
+        assert_eq!(
+            tour.distance as u64,
+            optimal_cost(&graph_map, &stops, false)
+        );
+        assert_eq!(tour.path.first(), tour.stops.first());
+        assert_eq!(tour.path.last(), tour.stops.last());
+
+        let options = TourOptions {
+            start: Some("E".to_string()),
+            end: Some("D".to_string()),
+            ..Default::default()
+        };
+        let tour = shortest_tour(&graph_map, &stops, &options).unwrap();
+        assert_eq!(tour.stops.first().unwrap(), "E");
+        assert_eq!(tour.stops.last().unwrap(), "D");
+        assert_eq!(tour.stops.len(), stops.len());
+
+        let options = TourOptions {
+            start: Some("G".to_string()),
+            end: Some("G".to_string()),
+            ..Default::default()
+        };
+        let tour = shortest_tour(&graph_map, &stops, &options).unwrap();
+        assert_eq!(tour.path.first().unwrap(), "G");
+        assert_eq!(tour.path.last().unwrap(), "G");
+        assert_eq!(tour.distance as u64, optimal_cost(&graph_map, &stops, true));
+    }
+
+    #[test]
+    fn improve_local_optimum_test() {
+        // asymmetric table, reversing a segment changes its cost
+        let count = 9;
+        let table: GraphMatrix = (0..count)
+            .map(|a| {
+                (0..count)
+                    .map(|b| ((a * 7 + b * 13) % 17 + 1) as u32)
+                    .collect()
+            })
+            .collect();
+
+        for closed in [false, true] {
+            let mut order: Vec<usize> = (0..count).collect();
+            let low = if closed { 1 } else { 0 };
+            improve(&table, &mut order, low, count - 1, closed);
+            let cost = tour_cost(&table, &order, closed);
+
+            // no single move found by recomputing the whole tour is better
+            for i in low..count - 1 {
+                for j in i + 1..count {
+                    let mut candidate = order.clone();
+                    candidate[i..=j].reverse();
+                    assert!(tour_cost(&table, &candidate, closed) >= cost);
+                }
+            }
+            for length in 1..=3 {
+                for i in low..=count - length {
+                    for position in low..=count - length {
+                        let mut candidate = order.clone();
+                        let segment: Vec<usize> = candidate.drain(i..i + length).collect();
+                        candidate.splice(position..position, segment);
+                        assert!(tour_cost(&table, &candidate, closed) >= cost);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tour_edge_cases_test() {
+        let (graph_map, _) = graph_samples::sample2();
+
+        let tour = shortest_tour(&graph_map, &["C"], &TourOptions::default()).unwrap();
+        assert_eq!(tour.stops, ["C"]);
+        assert_eq!(tour.distance, 0);
+        assert_eq!(tour.path, ["C"]);
+
+        assert!(shortest_tour(&graph_map, &["A", "X"], &TourOptions::default()).is_none());
+        assert!(shortest_tour(&graph_map, &[], &TourOptions::default()).is_none());
+    }
+}